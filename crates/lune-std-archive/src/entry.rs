@@ -0,0 +1,45 @@
+use mlua::prelude::*;
+
+use lune_utils::TableBuilder;
+
+/**
+    The kind of an entry in an archive.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveEntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+impl ArchiveEntryKind {
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Dir => "dir",
+            Self::Symlink => "symlink",
+        }
+    }
+}
+
+/**
+    An entry in an archive, as returned when listing its contents.
+*/
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub kind: ArchiveEntryKind,
+    pub size: u64,
+}
+
+impl IntoLua for ArchiveEntry {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        TableBuilder::new(lua.clone())?
+            .with_value("path", self.path)?
+            .with_value("kind", self.kind.name())?
+            .with_value("size", self.size)?
+            .build_readonly()
+            .map(LuaValue::Table)
+    }
+}
@@ -0,0 +1,34 @@
+use mlua::prelude::*;
+
+/**
+    An archive format supported by Lune.
+*/
+#[derive(Debug, Clone, Copy)]
+pub enum ArchiveFormat {
+    Tar,
+    Zip,
+}
+
+impl FromLua for ArchiveFormat {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        if let LuaValue::String(s) = &value {
+            match s.to_string_lossy().to_ascii_lowercase().trim() {
+                "tar" => Ok(Self::Tar),
+                "zip" => Ok(Self::Zip),
+                kind => Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "ArchiveFormat".to_string(),
+                    message: Some(format!(
+                        "Invalid format '{kind}', valid formats are: tar, zip"
+                    )),
+                }),
+            }
+        } else {
+            Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ArchiveFormat".to_string(),
+                message: None,
+            })
+        }
+    }
+}
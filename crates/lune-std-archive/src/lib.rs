@@ -0,0 +1,101 @@
+#![allow(clippy::cargo_common_metadata)]
+
+use std::path::PathBuf;
+
+use bstr::BString;
+use mlua::prelude::*;
+
+use lune_utils::TableBuilder;
+
+mod entry;
+mod format;
+mod source;
+mod tar;
+mod zip;
+
+pub use self::entry::{ArchiveEntry, ArchiveEntryKind};
+pub use self::format::ArchiveFormat;
+pub use self::source::{ArchiveSource, SourceEntry};
+
+const TYPEDEFS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/types.d.luau"));
+
+/**
+    Returns a string containing type definitions for the `archive` standard library.
+*/
+#[must_use]
+pub fn typedefs() -> String {
+    TYPEDEFS.to_string()
+}
+
+/**
+    Creates the `archive` standard library module.
+
+    # Errors
+
+    Errors when out of memory.
+*/
+pub fn module(lua: Lua) -> LuaResult<LuaTable> {
+    TableBuilder::new(lua)?
+        .with_async_function("list", archive_list)?
+        .with_async_function("read", archive_read)?
+        .with_async_function("extract", archive_extract)?
+        .with_async_function("create", archive_create)?
+        .build_readonly()
+}
+
+async fn archive_list(
+    _: Lua,
+    (format, bytes): (ArchiveFormat, BString),
+) -> LuaResult<Vec<ArchiveEntry>> {
+    let bytes = Vec::from(bytes);
+    blocking::unblock(move || match format {
+        ArchiveFormat::Tar => tar::list(bytes),
+        ArchiveFormat::Zip => zip::list(bytes),
+    })
+    .await
+    .into_lua_err()
+}
+
+async fn archive_read(
+    lua: Lua,
+    (format, bytes, path): (ArchiveFormat, BString, String),
+) -> LuaResult<Option<LuaString>> {
+    let bytes = Vec::from(bytes);
+    let contents = blocking::unblock(move || match format {
+        ArchiveFormat::Tar => tar::read(bytes, &path),
+        ArchiveFormat::Zip => zip::read(bytes, &path),
+    })
+    .await
+    .into_lua_err()?;
+    contents.map(|c| lua.create_string(c)).transpose()
+}
+
+async fn archive_extract(
+    _: Lua,
+    (format, bytes, dir): (ArchiveFormat, BString, String),
+) -> LuaResult<()> {
+    let bytes = Vec::from(bytes);
+    let dir = PathBuf::from(dir);
+    blocking::unblock(move || match format {
+        ArchiveFormat::Tar => tar::extract(bytes, &dir),
+        ArchiveFormat::Zip => zip::extract(bytes, &dir),
+    })
+    .await
+    .into_lua_err()
+}
+
+async fn archive_create(
+    lua: Lua,
+    (format, source): (ArchiveFormat, ArchiveSource),
+) -> LuaResult<LuaString> {
+    let bytes = blocking::unblock(move || {
+        let entries = source.collect()?;
+        match format {
+            ArchiveFormat::Tar => tar::create(entries),
+            ArchiveFormat::Zip => zip::create(entries),
+        }
+    })
+    .await
+    .into_lua_err()?;
+    lua.create_string(bytes)
+}
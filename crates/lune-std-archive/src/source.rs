@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    io::Error,
+    path::{Component, Path, PathBuf},
+};
+
+use bstr::BString;
+use mlua::prelude::*;
+
+const DEFAULT_FILE_MODE: u32 = 0o644;
+const DEFAULT_DIR_MODE: u32 = 0o755;
+
+/**
+    A single file or directory to add to a new archive.
+*/
+#[derive(Debug, Clone)]
+pub struct SourceEntry {
+    /// The path of the entry within the archive, always using `/` as the separator.
+    pub path: String,
+    /// The contents of the entry, or `None` if the entry is a directory.
+    pub contents: Option<Vec<u8>>,
+    pub mode: u32,
+}
+
+/**
+    The source of files and directories to create a new archive from.
+*/
+#[derive(Debug, Clone)]
+pub enum ArchiveSource {
+    Dir(PathBuf),
+    Files(Vec<(String, BString)>),
+}
+
+impl ArchiveSource {
+    /**
+        Collects all entries to add to the archive, sorted by path.
+
+        This reads all files in the source directory, and should be called on a blocking thread.
+
+        # Errors
+
+        Errors when the source directory could not be read, or when a path is invalid.
+    */
+    pub fn collect(self) -> Result<Vec<SourceEntry>, Error> {
+        match self {
+            Self::Dir(dir) => {
+                if !dir.is_dir() {
+                    return Err(Error::other(format!(
+                        "No directory exists at the path '{}'",
+                        dir.display()
+                    )));
+                }
+                let mut entries = Vec::new();
+                collect_dir(&dir, "", &mut entries)?;
+                Ok(entries)
+            }
+            Self::Files(mut files) => {
+                files.sort_by(|a, b| a.0.cmp(&b.0));
+                files
+                    .into_iter()
+                    .map(|(path, contents)| {
+                        validate_path(&path)?;
+                        Ok(SourceEntry {
+                            path,
+                            contents: Some(contents.into()),
+                            mode: DEFAULT_FILE_MODE,
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl FromLua for ArchiveSource {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::String(s) => Ok(Self::Dir(PathBuf::from(s.to_str()?.to_string()))),
+            LuaValue::Table(t) => {
+                let mut files = Vec::new();
+                for pair in t.pairs::<String, LuaValue>() {
+                    let (path, contents) = pair?;
+                    files.push((path, BString::from_lua(contents, lua)?));
+                }
+                Ok(Self::Files(files))
+            }
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ArchiveSource".to_string(),
+                message: Some(format!(
+                    "Invalid archive source - expected a directory path or a table of files, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
+
+fn collect_dir(dir: &Path, prefix: &str, entries: &mut Vec<SourceEntry>) -> Result<(), Error> {
+    let mut children = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    children.sort_by_key(fs::DirEntry::file_name);
+
+    for child in children {
+        let name = child.file_name().to_string_lossy().into_owned();
+        let path = format!("{prefix}{name}");
+        // NOTE: Symlinks are followed, and stored as the file or directory they point to
+        let meta = fs::metadata(child.path())?;
+        if meta.is_dir() {
+            entries.push(SourceEntry {
+                path: format!("{path}/"),
+                contents: None,
+                mode: file_mode(&meta).unwrap_or(DEFAULT_DIR_MODE),
+            });
+            collect_dir(&child.path(), &format!("{path}/"), entries)?;
+        } else {
+            entries.push(SourceEntry {
+                contents: Some(fs::read(child.path())?),
+                mode: file_mode(&meta).unwrap_or(DEFAULT_FILE_MODE),
+                path,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_path(path: &str) -> Result<(), Error> {
+    let is_valid = !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if is_valid {
+        Ok(())
+    } else {
+        Err(Error::other(format!(
+            "Invalid archive path '{path}' - paths must be relative and not contain '..'"
+        )))
+    }
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)]
+fn file_mode(meta: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(meta.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_: &fs::Metadata) -> Option<u32> {
+    None
+}
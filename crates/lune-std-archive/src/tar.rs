@@ -0,0 +1,97 @@
+use std::{
+    io::{Error, Read},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tar::{Archive, Builder, EntryType, Header};
+
+use super::{
+    entry::{ArchiveEntry, ArchiveEntryKind},
+    source::SourceEntry,
+};
+
+/**
+    Normalizes a path in a tar archive, which may have a leading
+    `./` or trailing `/`, into the same form as paths in zip archives.
+*/
+fn normalize_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let path = path.strip_prefix("./").unwrap_or(&path);
+    path.trim_end_matches('/').to_string()
+}
+
+pub fn list(bytes: Vec<u8>) -> Result<Vec<ArchiveEntry>, Error> {
+    let mut archive = Archive::new(bytes.as_slice());
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = normalize_path(&entry.path()?);
+        if path.is_empty() {
+            continue;
+        }
+        let kind = match entry.header().entry_type() {
+            EntryType::Directory => ArchiveEntryKind::Dir,
+            EntryType::Symlink | EntryType::Link => ArchiveEntryKind::Symlink,
+            _ => ArchiveEntryKind::File,
+        };
+        entries.push(ArchiveEntry {
+            path,
+            kind,
+            size: entry.size(),
+        });
+    }
+    Ok(entries)
+}
+
+pub fn read(bytes: Vec<u8>, path: &str) -> Result<Option<Vec<u8>>, Error> {
+    let mut archive = Archive::new(bytes.as_slice());
+    let path = path.trim_end_matches('/');
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if normalize_path(&entry.path()?) != path {
+            continue;
+        }
+        if entry.header().entry_type().is_dir() {
+            return Err(Error::other(format!(
+                "The archive entry at '{path}' is a directory"
+            )));
+        }
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        return Ok(Some(contents));
+    }
+    Ok(None)
+}
+
+pub fn extract(bytes: Vec<u8>, dir: &Path) -> Result<(), Error> {
+    // NOTE: Unpacking will skip any entries with paths
+    // that would end up outside of the target directory
+    Archive::new(bytes.as_slice()).unpack(dir)
+}
+
+pub fn create(entries: Vec<SourceEntry>) -> Result<Vec<u8>, Error> {
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut builder = Builder::new(Vec::new());
+    for entry in entries {
+        let mut header = Header::new_gnu();
+        header.set_mode(entry.mode);
+        header.set_mtime(mtime);
+        match entry.contents {
+            None => {
+                header.set_entry_type(EntryType::Directory);
+                header.set_size(0);
+                builder.append_data(&mut header, &entry.path, std::io::empty())?;
+            }
+            Some(contents) => {
+                header.set_entry_type(EntryType::Regular);
+                header.set_size(contents.len() as u64);
+                builder.append_data(&mut header, &entry.path, contents.as_slice())?;
+            }
+        }
+    }
+    builder.into_inner()
+}
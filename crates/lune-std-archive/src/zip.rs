@@ -0,0 +1,89 @@
+use std::{
+    io::{Cursor, Error, Read, Write},
+    path::Path,
+};
+
+use zip::{CompressionMethod, ZipArchive, ZipWriter, result::ZipError, write::SimpleFileOptions};
+
+use super::{
+    entry::{ArchiveEntry, ArchiveEntryKind},
+    source::SourceEntry,
+};
+
+fn open(bytes: Vec<u8>) -> Result<ZipArchive<Cursor<Vec<u8>>>, Error> {
+    ZipArchive::new(Cursor::new(bytes)).map_err(Error::other)
+}
+
+pub fn list(bytes: Vec<u8>) -> Result<Vec<ArchiveEntry>, Error> {
+    let mut archive = open(bytes)?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index).map_err(Error::other)?;
+        let kind = if file.is_dir() {
+            ArchiveEntryKind::Dir
+        } else if file.is_symlink() {
+            ArchiveEntryKind::Symlink
+        } else {
+            ArchiveEntryKind::File
+        };
+        entries.push(ArchiveEntry {
+            path: file.name().trim_end_matches('/').to_string(),
+            kind,
+            size: file.size(),
+        });
+    }
+    Ok(entries)
+}
+
+pub fn read(bytes: Vec<u8>, path: &str) -> Result<Option<Vec<u8>>, Error> {
+    let mut archive = open(bytes)?;
+    let path = path.trim_end_matches('/');
+    // NOTE: Directory names in zip archives always end with a
+    // trailing slash, so those must be looked up separately
+    if archive.index_for_name(&format!("{path}/")).is_some() {
+        return Err(Error::other(format!(
+            "The archive entry at '{path}' is a directory"
+        )));
+    }
+    let mut file = match archive.by_name(path) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(Error::other(e)),
+    };
+    if file.is_dir() {
+        return Err(Error::other(format!(
+            "The archive entry at '{path}' is a directory"
+        )));
+    }
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)?;
+    Ok(Some(contents))
+}
+
+pub fn extract(bytes: Vec<u8>, dir: &Path) -> Result<(), Error> {
+    // NOTE: Extracting will skip any entries with paths
+    // that would end up outside of the target directory
+    open(bytes)?.extract(dir).map_err(Error::other)
+}
+
+pub fn create(entries: Vec<SourceEntry>) -> Result<Vec<u8>, Error> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for entry in entries {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(entry.mode);
+        match entry.contents {
+            None => writer
+                .add_directory(entry.path, options)
+                .map_err(Error::other)?,
+            Some(contents) => {
+                writer
+                    .start_file(entry.path, options)
+                    .map_err(Error::other)?;
+                writer.write_all(&contents)?;
+            }
+        }
+    }
+    let cursor = writer.finish().map_err(Error::other)?;
+    Ok(cursor.into_inner())
+}
@@ -188,6 +188,18 @@ impl DateTime {
         Ok(Self { inner })
     }
 
+    /**
+        Extracts the whole seconds and the remaining nanoseconds
+        passed since the UNIX epoch from this `DateTime`.
+
+        See [`chrono::DateTime::timestamp`] and [`chrono::DateTime::timestamp_subsec_nanos`]
+        for additional details.
+    */
+    #[must_use]
+    pub fn to_unix_timestamp_parts(self) -> (i64, u32) {
+        (self.inner.timestamp(), self.inner.timestamp_subsec_nanos())
+    }
+
     /**
         Extracts individual date & time values from this
         `DateTime`, using the current local time zone.
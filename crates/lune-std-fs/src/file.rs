@@ -0,0 +1,224 @@
+use std::io::{Error, SeekFrom};
+use std::sync::Arc;
+
+use async_fs as fs;
+use async_lock::Mutex as AsyncMutex;
+use bstr::BString;
+use futures_lite::prelude::*;
+use mlua::prelude::*;
+
+const DEFAULT_BUFFER_SIZE: usize = 8192;
+
+// Inner (plumbing) implementation
+
+#[derive(Debug)]
+struct FileInner {
+    file: Option<fs::File>,
+    // Bytes that were read from the file ahead of the current logical position,
+    // this is only ever filled by `read_line` and drained by subsequent reads
+    buffer: Vec<u8>,
+}
+
+impl FileInner {
+    fn file(&mut self) -> Result<&mut fs::File, Error> {
+        self.file
+            .as_mut()
+            .ok_or_else(|| Error::other("File has been closed"))
+    }
+
+    /**
+        Moves the real file cursor back to the logical position,
+        discarding any bytes that were read ahead of it.
+    */
+    async fn discard_buffer(&mut self) -> Result<(), Error> {
+        if !self.buffer.is_empty() {
+            let offset = self.buffer.len() as i64;
+            self.buffer.clear();
+            self.file()?.seek(SeekFrom::Current(-offset)).await?;
+        }
+        Ok(())
+    }
+
+    async fn read(&mut self, size: usize) -> Result<Vec<u8>, Error> {
+        if !self.buffer.is_empty() {
+            let len = size.min(self.buffer.len());
+            return Ok(self.buffer.drain(..len).collect());
+        }
+
+        // NOTE: The size may be much larger than the file, so we must not allocate
+        // all of it up front, and instead let the buffer grow as data arrives
+        let mut buf = Vec::with_capacity(size.min(DEFAULT_BUFFER_SIZE));
+        let file = self.file()?;
+        file.take(size as u64).read_to_end(&mut buf).await?;
+
+        Ok(buf)
+    }
+
+    async fn read_line(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        let mut searched = 0;
+        loop {
+            if let Some(pos) = self.buffer[searched..].iter().position(|b| *b == b'\n') {
+                let end = searched + pos;
+                let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                return Ok(Some(line));
+            }
+            searched = self.buffer.len();
+
+            let mut chunk = vec![0; DEFAULT_BUFFER_SIZE];
+            let read = self.file()?.read(&mut chunk).await?;
+            if read == 0 {
+                // Reached EOF, return whatever is left as the last line
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some(std::mem::take(&mut self.buffer)));
+            }
+
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    async fn write(&mut self, data: Vec<u8>) -> Result<(), Error> {
+        self.discard_buffer().await?;
+        self.file()?.write_all(&data).await
+    }
+
+    async fn seek(&mut self, from: SeekFrom) -> Result<u64, Error> {
+        // Relative seeks must account for any bytes read ahead of the logical position
+        let from = match from {
+            SeekFrom::Current(offset) => SeekFrom::Current(offset - self.buffer.len() as i64),
+            other => other,
+        };
+        self.buffer.clear();
+        self.file()?.seek(from).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        self.file()?.flush().await
+    }
+
+    async fn truncate(&mut self, size: Option<u64>) -> Result<(), Error> {
+        self.discard_buffer().await?;
+        let file = self.file()?;
+        // Any pending writes must be flushed before truncating, or they would
+        // be written past the new end of the file once the cursor is flushed
+        file.flush().await?;
+        let size = match size {
+            Some(size) => size,
+            None => file.seek(SeekFrom::Current(0)).await?,
+        };
+        file.set_len(size).await
+    }
+
+    async fn close(&mut self) -> Result<(), Error> {
+        self.buffer.clear();
+        if let Some(mut file) = self.file.take() {
+            file.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+// Outer (lua-accessible, clonable) implementation
+
+#[derive(Debug, Clone)]
+pub struct File {
+    inner: Arc<AsyncMutex<FileInner>>,
+}
+
+impl LuaUserData for File {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_async_method("read", |lua, this, size: Option<usize>| {
+            let inner = this.inner.clone();
+            let size = size.unwrap_or(DEFAULT_BUFFER_SIZE);
+            async move {
+                let mut inner = inner.lock().await;
+                let bytes = inner.read(size).await.into_lua_err()?;
+                if bytes.is_empty() && size > 0 {
+                    Ok(LuaValue::Nil)
+                } else {
+                    Ok(LuaValue::String(lua.create_string(bytes)?))
+                }
+            }
+        });
+        methods.add_async_method("readLine", |lua, this, (): ()| {
+            let inner = this.inner.clone();
+            async move {
+                let mut inner = inner.lock().await;
+                match inner.read_line().await.into_lua_err()? {
+                    Some(line) => Ok(LuaValue::String(lua.create_string(line)?)),
+                    None => Ok(LuaValue::Nil),
+                }
+            }
+        });
+        methods.add_async_method("write", |_, this, data: BString| {
+            let inner = this.inner.clone();
+            let data = data.to_vec();
+            async move {
+                let mut inner = inner.lock().await;
+                inner.write(data).await.into_lua_err()
+            }
+        });
+        methods.add_async_method(
+            "seek",
+            |_, this, (whence, offset): (Option<String>, Option<i64>)| {
+                let inner = this.inner.clone();
+                async move {
+                    let offset = offset.unwrap_or(0);
+                    let from = match whence.as_deref().unwrap_or("current") {
+                        "set" => SeekFrom::Start(u64::try_from(offset).map_err(|_| {
+                            LuaError::RuntimeError(format!(
+                                "Invalid offset - cannot seek to negative position {offset}"
+                            ))
+                        })?),
+                        "current" => SeekFrom::Current(offset),
+                        "end" => SeekFrom::End(offset),
+                        other => {
+                            return Err(LuaError::RuntimeError(format!(
+                                "Invalid seek origin - expected one of 'set', 'current', 'end', got '{other}'"
+                            )));
+                        }
+                    };
+                    let mut inner = inner.lock().await;
+                    inner.seek(from).await.into_lua_err()
+                }
+            },
+        );
+        methods.add_async_method("flush", |_, this, (): ()| {
+            let inner = this.inner.clone();
+            async move {
+                let mut inner = inner.lock().await;
+                inner.flush().await.into_lua_err()
+            }
+        });
+        methods.add_async_method("truncate", |_, this, size: Option<u64>| {
+            let inner = this.inner.clone();
+            async move {
+                let mut inner = inner.lock().await;
+                inner.truncate(size).await.into_lua_err()
+            }
+        });
+        methods.add_async_method("close", |_, this, (): ()| {
+            let inner = this.inner.clone();
+            async move {
+                let mut inner = inner.lock().await;
+                inner.close().await.into_lua_err()
+            }
+        });
+    }
+}
+
+impl From<fs::File> for File {
+    fn from(file: fs::File) -> Self {
+        Self {
+            inner: Arc::new(AsyncMutex::new(FileInner {
+                file: Some(file),
+                buffer: Vec::new(),
+            })),
+        }
+    }
+}
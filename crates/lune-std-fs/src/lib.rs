@@ -11,12 +11,23 @@ use mlua::prelude::*;
 use lune_utils::TableBuilder;
 
 mod copy;
+mod file;
 mod metadata;
 mod options;
+mod temp;
+mod walk;
+mod watch;
 
 use self::copy::copy;
+use self::file::File;
 use self::metadata::FsMetadata;
-use self::options::FsWriteOptions;
+use self::options::{
+    FsMetadataOptions, FsOpenMode, FsPermissionsUpdate, FsTempOptions, FsTimesUpdate,
+    FsWalkOptions, FsWriteFileOptions, FsWriteOptions,
+};
+use self::temp::TempHandle;
+use self::walk::DirWalker;
+use self::watch::{config::WatchConfig, handle::WatchHandle};
 
 const TYPEDEFS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/types.d.luau"));
 
@@ -28,6 +39,16 @@ pub fn typedefs() -> String {
     TYPEDEFS.to_string()
 }
 
+/**
+    Removes any temporary files and directories created using `fs.tempDir`
+    and `fs.tempFile` that were not already closed by the script.
+
+    Should be called once the runtime has finished running all threads.
+*/
+pub fn cleanup(lua: &Lua) {
+    temp::cleanup(lua);
+}
+
 /**
     Creates the `fs` standard library module.
 
@@ -44,10 +65,22 @@ pub fn module(lua: Lua) -> LuaResult<LuaTable> {
         .with_async_function("removeFile", fs_remove_file)?
         .with_async_function("removeDir", fs_remove_dir)?
         .with_async_function("metadata", fs_metadata)?
+        .with_async_function("setPermissions", fs_set_permissions)?
+        .with_async_function("setTimes", fs_set_times)?
         .with_async_function("isFile", fs_is_file)?
         .with_async_function("isDir", fs_is_dir)?
         .with_async_function("move", fs_move)?
         .with_async_function("copy", fs_copy)?
+        .with_async_function("symlink", fs_symlink)?
+        .with_async_function("hardlink", fs_hardlink)?
+        .with_async_function("readLink", fs_read_link)?
+        .with_async_function("canonicalize", fs_canonicalize)?
+        .with_async_function("open", fs_open)?
+        .with_function("watch", fs_watch)?
+        .with_async_function("walk", fs_walk)?
+        .with_async_function("glob", fs_glob)?
+        .with_async_function("tempDir", fs_temp_dir)?
+        .with_async_function("tempFile", fs_temp_file)?
         .build_readonly()
 }
 
@@ -73,8 +106,15 @@ async fn fs_read_dir(_: Lua, path: String) -> LuaResult<Vec<String>> {
     Ok(dir_strings)
 }
 
-async fn fs_write_file(_: Lua, (path, contents): (String, BString)) -> LuaResult<()> {
-    fs::write(&path, contents.as_bytes()).await.into_lua_err()
+async fn fs_write_file(
+    _: Lua,
+    (path, contents, options): (String, BString, FsWriteFileOptions),
+) -> LuaResult<()> {
+    if options.atomic {
+        temp::write_file_atomic(PathBuf::from(path), contents.into()).await
+    } else {
+        fs::write(&path, contents.as_bytes()).await.into_lua_err()
+    }
 }
 
 async fn fs_write_dir(_: Lua, path: String) -> LuaResult<()> {
@@ -89,14 +129,66 @@ async fn fs_remove_dir(_: Lua, path: String) -> LuaResult<()> {
     fs::remove_dir_all(&path).await.into_lua_err()
 }
 
-async fn fs_metadata(_: Lua, path: String) -> LuaResult<FsMetadata> {
-    match fs::metadata(path).await {
+async fn fs_metadata(
+    _: Lua,
+    (path, options): (String, FsMetadataOptions),
+) -> LuaResult<FsMetadata> {
+    let result = if options.follow_symlinks {
+        fs::metadata(path).await
+    } else {
+        fs::symlink_metadata(path).await
+    };
+    match result {
         Err(e) if e.kind() == IoErrorKind::NotFound => Ok(FsMetadata::not_found()),
         Ok(meta) => Ok(FsMetadata::from(meta)),
         Err(e) => Err(e.into()),
     }
 }
 
+async fn fs_set_permissions(
+    _: Lua,
+    (path, update): (String, FsPermissionsUpdate),
+) -> LuaResult<()> {
+    let mut permissions = fs::metadata(&path).await.into_lua_err()?.permissions();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Some(mode) = update.mode {
+            permissions.set_mode(mode);
+        } else if let Some(read_only) = update.read_only {
+            // NOTE: Making a file writable using set_readonly would make it
+            // writable for all users, so we only give back write to the owner
+            let mode = permissions.mode();
+            permissions.set_mode(if read_only {
+                mode & !0o222
+            } else {
+                mode | 0o200
+            });
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        if let Some(read_only) = update.read_only {
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(read_only);
+        }
+    }
+
+    fs::set_permissions(&path, permissions).await.into_lua_err()
+}
+
+async fn fs_set_times(_: Lua, (path, update): (String, FsTimesUpdate)) -> LuaResult<()> {
+    blocking::unblock(move || match (update.accessed_at, update.modified_at) {
+        (Some(atime), Some(mtime)) => filetime::set_file_times(&path, atime, mtime),
+        (Some(atime), None) => filetime::set_file_atime(&path, atime),
+        (None, Some(mtime)) => filetime::set_file_mtime(&path, mtime),
+        (None, None) => Ok(()),
+    })
+    .await
+    .into_lua_err()
+}
+
 async fn fs_is_file(_: Lua, path: String) -> LuaResult<bool> {
     match fs::metadata(path).await {
         Err(e) if e.kind() == IoErrorKind::NotFound => Ok(false),
@@ -135,3 +227,76 @@ async fn fs_move(_: Lua, (from, to, options): (String, String, FsWriteOptions))
 async fn fs_copy(_: Lua, (from, to, options): (String, String, FsWriteOptions)) -> LuaResult<()> {
     copy(from, to, options).await
 }
+
+async fn fs_open(_: Lua, (path, mode): (String, FsOpenMode)) -> LuaResult<File> {
+    let file = mode.to_open_options().open(&path).await.into_lua_err()?;
+    Ok(File::from(file))
+}
+
+fn fs_watch(lua: &Lua, (path, config): (String, WatchConfig)) -> LuaResult<WatchHandle> {
+    watch::watch(lua.clone(), PathBuf::from(path), config)
+}
+
+async fn fs_walk(_: Lua, (dir, options): (String, FsWalkOptions)) -> LuaResult<DirWalker> {
+    walk::walk(PathBuf::from(dir), options).await
+}
+
+async fn fs_glob(_: Lua, (pattern, options): (String, FsWalkOptions)) -> LuaResult<DirWalker> {
+    walk::glob(pattern, options).await
+}
+
+async fn fs_symlink(_: Lua, (target, link): (String, String)) -> LuaResult<()> {
+    #[cfg(unix)]
+    {
+        fs::unix::symlink(target, link).await.into_lua_err()
+    }
+    #[cfg(windows)]
+    {
+        // NOTE: Windows needs to know if the target is a directory or not, and
+        // relative targets are relative to the directory that the link is in
+        let link_path = PathBuf::from(&link);
+        let target_path = match link_path.parent() {
+            Some(parent) => parent.join(&target),
+            None => PathBuf::from(&target),
+        };
+        if fs::metadata(target_path)
+            .await
+            .is_ok_and(|meta| meta.is_dir())
+        {
+            fs::windows::symlink_dir(target, link).await.into_lua_err()
+        } else {
+            fs::windows::symlink_file(target, link).await.into_lua_err()
+        }
+    }
+}
+
+async fn fs_hardlink(_: Lua, (from, to): (String, String)) -> LuaResult<()> {
+    fs::hard_link(from, to).await.into_lua_err()
+}
+
+async fn fs_read_link(_: Lua, path: String) -> LuaResult<String> {
+    let target = fs::read_link(&path).await.into_lua_err()?;
+    path_to_string(target)
+}
+
+async fn fs_canonicalize(_: Lua, path: String) -> LuaResult<String> {
+    let canonical = fs::canonicalize(&path).await.into_lua_err()?;
+    path_to_string(canonical)
+}
+
+fn path_to_string(path: PathBuf) -> LuaResult<String> {
+    path.into_os_string().into_string().map_err(|path| {
+        LuaError::RuntimeError(format!(
+            "Path could not be converted into a string: '{}'",
+            path.to_string_lossy()
+        ))
+    })
+}
+
+async fn fs_temp_dir(lua: Lua, options: FsTempOptions) -> LuaResult<TempHandle> {
+    temp::create_temp_dir(lua, options).await
+}
+
+async fn fs_temp_file(lua: Lua, options: FsTempOptions) -> LuaResult<TempHandle> {
+    temp::create_temp_file(lua, options).await
+}
@@ -16,6 +16,7 @@ pub enum FsMetadataKind {
     File,
     Dir,
     Symlink,
+    Other,
 }
 
 impl fmt::Display for FsMetadataKind {
@@ -28,6 +29,7 @@ impl fmt::Display for FsMetadataKind {
                 Self::File => "file",
                 Self::Dir => "dir",
                 Self::Symlink => "symlink",
+                Self::Other => "other",
             }
         )
     }
@@ -42,6 +44,7 @@ impl FromStr for FsMetadataKind {
             "file" => Ok(Self::File),
             "dir" => Ok(Self::Dir),
             "symlink" => Ok(Self::Symlink),
+            "other" => Ok(Self::Other),
             _ => Err("Invalid metadata kind"),
         }
     }
@@ -56,7 +59,8 @@ impl From<StdFileType> for FsMetadataKind {
         } else if value.is_symlink() {
             Self::Symlink
         } else {
-            panic!("Encountered unknown filesystem filetype")
+            // NOTE: Special files such as fifos, sockets, and devices
+            Self::Other
         }
     }
 }
@@ -74,20 +78,31 @@ impl IntoLua for FsMetadataKind {
 #[derive(Debug, Clone)]
 pub struct FsPermissions {
     pub(crate) read_only: bool,
+    pub(crate) mode: Option<u32>,
 }
 
 impl From<StdPermissions> for FsPermissions {
     fn from(value: StdPermissions) -> Self {
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            Some(value.mode() & 0o7777)
+        };
+        #[cfg(not(unix))]
+        let mode = None;
+
         Self {
             read_only: value.readonly(),
+            mode,
         }
     }
 }
 
 impl IntoLua for FsPermissions {
     fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
-        let tab = lua.create_table_with_capacity(0, 1)?;
+        let tab = lua.create_table_with_capacity(0, 2)?;
         tab.set("readOnly", self.read_only)?;
+        tab.set("mode", self.mode)?;
         tab.set_readonly(true);
         Ok(LuaValue::Table(tab))
     }
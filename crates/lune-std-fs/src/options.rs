@@ -1,4 +1,9 @@
+use async_fs as fs;
+use filetime::FileTime;
 use mlua::prelude::*;
+use tempfile::Builder as TempBuilder;
+
+use lune_std_datetime::DateTime;
 
 #[derive(Debug, Clone, Copy)]
 pub struct FsWriteOptions {
@@ -29,3 +34,310 @@ impl FromLua for FsWriteOptions {
         })
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsOpenMode {
+    Read,
+    Write,
+    Append,
+    ReadWrite,
+    ReadWriteTruncate,
+    ReadAppend,
+}
+
+impl FsOpenMode {
+    pub fn to_open_options(self) -> fs::OpenOptions {
+        let mut options = fs::OpenOptions::new();
+        match self {
+            Self::Read => options.read(true),
+            Self::Write => options.write(true).create(true).truncate(true),
+            Self::Append => options.append(true).create(true),
+            Self::ReadWrite => options.read(true).write(true),
+            Self::ReadWriteTruncate => options.read(true).write(true).create(true).truncate(true),
+            Self::ReadAppend => options.read(true).append(true).create(true),
+        };
+        options
+    }
+}
+
+impl FromLua for FsOpenMode {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let mode = match &value {
+            LuaValue::Nil => return Ok(Self::Read),
+            LuaValue::String(s) => s.to_str()?.to_string(),
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "FsOpenMode".to_string(),
+                    message: Some(format!(
+                        "Invalid open mode - expected string, got {}",
+                        value.type_name()
+                    )),
+                });
+            }
+        };
+        // NOTE: A single "b" is accepted anywhere after the first character, as in
+        // "rb+" or "r+b", for familiarity with io.open - files are always opened
+        // in binary mode anyway
+        let stripped = match mode.get(1..) {
+            Some(rest) => format!("{}{}", &mode[..1], rest.replacen('b', "", 1)),
+            None => mode.clone(),
+        };
+        Ok(match stripped.as_str() {
+            "r" => Self::Read,
+            "w" => Self::Write,
+            "a" => Self::Append,
+            "r+" => Self::ReadWrite,
+            "w+" => Self::ReadWriteTruncate,
+            "a+" => Self::ReadAppend,
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "FsOpenMode".to_string(),
+                    message: Some(format!(
+                        "Invalid open mode - expected one of 'r', 'w', 'a', 'r+', 'w+', 'a+', got '{mode}'"
+                    )),
+                });
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FsWalkOptions {
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) follow_symlinks: bool,
+    pub(crate) include_hidden: bool,
+    pub(crate) exclude: Vec<String>,
+}
+
+impl FromLua for FsWalkOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        Ok(match value {
+            LuaValue::Nil => Self {
+                include_hidden: true,
+                ..Self::default()
+            },
+            LuaValue::Table(t) => {
+                let max_depth: Option<usize> = t.get("maxDepth")?;
+                let follow_symlinks: Option<bool> = t.get("followSymlinks")?;
+                let include_hidden: Option<bool> = t.get("includeHidden")?;
+                let exclude: Option<Vec<String>> = t.get("exclude")?;
+                Self {
+                    max_depth,
+                    follow_symlinks: follow_symlinks.unwrap_or(false),
+                    include_hidden: include_hidden.unwrap_or(true),
+                    exclude: exclude.unwrap_or_default(),
+                }
+            }
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "FsWalkOptions".to_string(),
+                    message: Some(format!(
+                        "Invalid walk options - expected table, got {}",
+                        value.type_name()
+                    )),
+                });
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FsMetadataOptions {
+    pub(crate) follow_symlinks: bool,
+}
+
+impl FromLua for FsMetadataOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        Ok(match value {
+            LuaValue::Nil => Self {
+                follow_symlinks: true,
+            },
+            LuaValue::Table(t) => {
+                let follow_symlinks: Option<bool> = t.get("followSymlinks")?;
+                Self {
+                    follow_symlinks: follow_symlinks.unwrap_or(true),
+                }
+            }
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "FsMetadataOptions".to_string(),
+                    message: Some(format!(
+                        "Invalid metadata options - expected table, got {}",
+                        value.type_name()
+                    )),
+                });
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FsPermissionsUpdate {
+    pub(crate) read_only: Option<bool>,
+    pub(crate) mode: Option<u32>,
+}
+
+impl FromLua for FsPermissionsUpdate {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let LuaValue::Table(t) = &value else {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "FsPermissionsUpdate".to_string(),
+                message: Some(format!(
+                    "Invalid permissions - expected table, got {}",
+                    value.type_name()
+                )),
+            });
+        };
+
+        let read_only: Option<bool> = t.get("readOnly")?;
+        let mode: Option<u32> = t.get("mode")?;
+        if read_only.is_some() && mode.is_some() {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "FsPermissionsUpdate".to_string(),
+                message: Some(String::from(
+                    "Invalid permissions - 'readOnly' and 'mode' can not both be given",
+                )),
+            });
+        }
+        if cfg!(not(unix)) && mode.is_some() {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "FsPermissionsUpdate".to_string(),
+                message: Some(String::from(
+                    "Invalid permissions - 'mode' is only supported on unix platforms",
+                )),
+            });
+        }
+
+        Ok(Self { read_only, mode })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FsTimesUpdate {
+    pub(crate) accessed_at: Option<FileTime>,
+    pub(crate) modified_at: Option<FileTime>,
+}
+
+impl FromLua for FsTimesUpdate {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let LuaValue::Table(t) = &value else {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "FsTimesUpdate".to_string(),
+                message: Some(format!(
+                    "Invalid times - expected table, got {}",
+                    value.type_name()
+                )),
+            });
+        };
+
+        Ok(Self {
+            accessed_at: file_time_from_lua(t.get("accessedAt")?)?,
+            modified_at: file_time_from_lua(t.get("modifiedAt")?)?,
+        })
+    }
+}
+
+fn file_time_from_lua(value: LuaValue) -> LuaResult<Option<FileTime>> {
+    Ok(match value {
+        LuaValue::Nil => None,
+        LuaValue::Integer(i) => Some(FileTime::from_unix_time(i, 0)),
+        LuaValue::Number(n) => {
+            let secs = n.floor();
+            let nanos = ((n - secs) * 1_000_000_000f64).round() as u32;
+            Some(FileTime::from_unix_time(
+                secs as i64,
+                nanos.min(999_999_999),
+            ))
+        }
+        LuaValue::UserData(ud) if ud.is::<DateTime>() => {
+            let (secs, nanos) = ud.borrow::<DateTime>()?.to_unix_timestamp_parts();
+            Some(FileTime::from_unix_time(secs, nanos))
+        }
+        _ => {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "FileTime".to_string(),
+                message: Some(format!(
+                    "Invalid time - expected DateTime or number, got {}",
+                    value.type_name()
+                )),
+            });
+        }
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsWriteFileOptions {
+    pub(crate) atomic: bool,
+}
+
+impl FromLua for FsWriteFileOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        Ok(match value {
+            LuaValue::Nil => Self::default(),
+            LuaValue::Table(t) => {
+                let atomic: Option<bool> = t.get("atomic")?;
+                Self {
+                    atomic: atomic.unwrap_or(false),
+                }
+            }
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "FsWriteFileOptions".to_string(),
+                    message: Some(format!(
+                        "Invalid write file options - expected table, got {}",
+                        value.type_name()
+                    )),
+                });
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FsTempOptions {
+    pub(crate) prefix: Option<String>,
+    pub(crate) suffix: Option<String>,
+}
+
+impl FsTempOptions {
+    pub fn to_builder(&self) -> TempBuilder<'_, '_> {
+        let mut builder = TempBuilder::new();
+        builder.prefix(self.prefix.as_deref().unwrap_or("lune-"));
+        if let Some(suffix) = &self.suffix {
+            builder.suffix(suffix);
+        }
+        builder
+    }
+}
+
+impl FromLua for FsTempOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        Ok(match value {
+            LuaValue::Nil => Self::default(),
+            LuaValue::Table(t) => Self {
+                prefix: t.get("prefix")?,
+                suffix: t.get("suffix")?,
+            },
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "FsTempOptions".to_string(),
+                    message: Some(format!(
+                        "Invalid temp options - expected table, got {}",
+                        value.type_name()
+                    )),
+                });
+            }
+        })
+    }
+}
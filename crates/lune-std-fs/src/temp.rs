@@ -0,0 +1,178 @@
+use std::{
+    collections::HashSet,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use async_fs as fs;
+use mlua::prelude::*;
+use tempfile::Builder as TempBuilder;
+
+use super::options::FsTempOptions;
+
+/**
+    Paths to temporary files and directories that have not yet been removed.
+
+    Stored in Lua app data, so that any leftovers can be removed when the runtime finishes.
+*/
+#[derive(Debug, Clone, Default)]
+struct TempPaths {
+    inner: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl TempPaths {
+    fn get_or_init(lua: &Lua) -> Self {
+        if let Some(paths) = lua.app_data_ref::<Self>() {
+            return paths.clone();
+        }
+        let paths = Self::default();
+        lua.set_app_data(paths.clone());
+        paths
+    }
+
+    fn insert(&self, path: PathBuf) {
+        self.inner.lock().expect("poisoned").insert(path);
+    }
+
+    fn remove(&self, path: &Path) -> bool {
+        self.inner.lock().expect("poisoned").remove(path)
+    }
+
+    fn take_all(&self) -> Vec<PathBuf> {
+        self.inner.lock().expect("poisoned").drain().collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TempHandle {
+    path: PathBuf,
+    is_dir: bool,
+    paths: TempPaths,
+}
+
+impl TempHandle {
+    async fn close(&self) -> LuaResult<()> {
+        // NOTE: Only the first call to close will actually remove the path,
+        // any other calls or the runtime cleanup will then be a no-op
+        if !self.paths.remove(&self.path) {
+            return Ok(());
+        }
+        let result = if self.is_dir {
+            fs::remove_dir_all(&self.path).await
+        } else {
+            fs::remove_file(&self.path).await
+        };
+        match result {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl LuaUserData for TempHandle {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("path", |_, this| {
+            Ok(this.path.to_string_lossy().into_owned())
+        });
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_async_method("close", |_, this, (): ()| {
+            let this = this.clone();
+            async move { this.close().await }
+        });
+    }
+}
+
+/**
+    Creates a new temporary directory, which is removed
+    when closed or when the Lune runtime finishes.
+*/
+pub async fn create_temp_dir(lua: Lua, options: FsTempOptions) -> LuaResult<TempHandle> {
+    let path =
+        blocking::unblock(move || options.to_builder().tempdir().map(tempfile::TempDir::keep))
+            .await?;
+    Ok(register(&lua, path, true))
+}
+
+/**
+    Creates a new, empty temporary file, which is removed
+    when closed or when the Lune runtime finishes.
+*/
+pub async fn create_temp_file(lua: Lua, options: FsTempOptions) -> LuaResult<TempHandle> {
+    let path = blocking::unblock(move || {
+        options
+            .to_builder()
+            .tempfile()
+            .and_then(|file| file.keep().map_err(|e| e.error))
+            .map(|(_, path)| path)
+    })
+    .await?;
+    Ok(register(&lua, path, false))
+}
+
+fn register(lua: &Lua, path: PathBuf, is_dir: bool) -> TempHandle {
+    let paths = TempPaths::get_or_init(lua);
+    paths.insert(path.clone());
+    TempHandle {
+        path,
+        is_dir,
+        paths,
+    }
+}
+
+/**
+    Writes contents to a temporary sibling of the given path, and then renames
+    it into place, so that the file is never observed as partially written.
+*/
+pub async fn write_file_atomic(path: PathBuf, contents: Vec<u8>) -> LuaResult<()> {
+    blocking::unblock(move || {
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        // NOTE: Temporary files are only accessible by their owner, but the
+        // written file should keep the permissions of the file it replaces,
+        // or get the same permissions as a normal write would give it
+        let existing = std::fs::metadata(&path).ok().map(|meta| meta.permissions());
+        let mut builder = TempBuilder::new();
+        builder.prefix(".lune-");
+        #[cfg(unix)]
+        if existing.is_none() {
+            use std::os::unix::fs::PermissionsExt;
+            builder.permissions(std::fs::Permissions::from_mode(0o666));
+        }
+
+        let mut file = builder.tempfile_in(dir)?;
+        if let Some(permissions) = existing {
+            file.as_file().set_permissions(permissions)?;
+        }
+        file.write_all(&contents)?;
+        file.as_file().sync_all()?;
+        file.persist(&path).map_err(|e| e.error)?;
+        Ok::<_, std::io::Error>(())
+    })
+    .await
+    .into_lua_err()
+}
+
+/**
+    Removes all temporary files and directories that were
+    created using the given Lua state, and not yet closed.
+*/
+pub fn cleanup(lua: &Lua) {
+    let Some(paths) = lua.app_data_ref::<TempPaths>().map(|paths| paths.clone()) else {
+        return;
+    };
+    for path in paths.take_all() {
+        // NOTE: This runs while the runtime is shutting down,
+        // so there is nobody to report any errors to here
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path).ok();
+        } else {
+            std::fs::remove_file(&path).ok();
+        }
+    }
+}
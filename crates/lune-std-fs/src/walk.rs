@@ -0,0 +1,215 @@
+use std::path::{Component, Path, PathBuf};
+
+use async_channel::{Receiver, Sender, bounded};
+use async_fs as fs;
+use globset::{GlobBuilder, GlobMatcher};
+use ignore::{DirEntry, WalkBuilder, overrides::OverrideBuilder};
+use mlua::prelude::*;
+
+use lune_utils::TableBuilder;
+
+use super::{metadata::FsMetadata, options::FsWalkOptions};
+
+// NOTE: Walking happens on a separate thread, and this limits how far
+// ahead of the lua side it may get before it waits for entries to be read
+const CHANNEL_CAPACITY: usize = 64;
+
+const GLOB_META_CHARS: &[char] = &['*', '?', '[', ']', '{', '}'];
+
+#[derive(Debug)]
+pub struct WalkEntry {
+    path: PathBuf,
+    depth: usize,
+    metadata: FsMetadata,
+}
+
+impl IntoLua for WalkEntry {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        TableBuilder::new(lua.clone())?
+            .with_value("path", self.path.to_string_lossy().into_owned())?
+            .with_value("depth", self.depth)?
+            .with_value("metadata", self.metadata)?
+            .build_readonly()
+            .map(LuaValue::Table)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DirWalker {
+    entries: Receiver<Result<WalkEntry, String>>,
+}
+
+impl LuaUserData for DirWalker {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_async_method("next", |_, this, (): ()| {
+            let entries = this.entries.clone();
+            async move {
+                match entries.recv().await {
+                    Ok(Ok(entry)) => Ok(Some(entry)),
+                    Ok(Err(e)) => Err(LuaError::RuntimeError(e)),
+                    // NOTE: The walker thread has finished and all entries were read
+                    Err(_) => Ok(None),
+                }
+            }
+        });
+    }
+}
+
+/**
+    Walks the given directory recursively, streaming entries
+    and their metadata back using the returned `DirWalker`.
+*/
+pub async fn walk(dir: PathBuf, options: FsWalkOptions) -> LuaResult<DirWalker> {
+    ensure_dir_exists(&dir).await?;
+    start_walker(dir, options, None)
+}
+
+/**
+    Finds all paths matching the given glob pattern, streaming
+    entries and their metadata back using the returned `DirWalker`.
+*/
+pub async fn glob(pattern: String, mut options: FsWalkOptions) -> LuaResult<DirWalker> {
+    let matcher = GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| LuaError::RuntimeError(format!("Invalid glob pattern '{pattern}'\n{e}")))?
+        .compile_matcher();
+
+    // Walk only the directory containing the glob, and if there is no
+    // recursive wildcard, only as deep as the pattern could possibly match
+    let (base, rest) = split_glob_pattern(&pattern);
+    if !rest.iter().any(|component| component == "**") {
+        let depth = rest.len();
+        options.max_depth = Some(options.max_depth.map_or(depth, |max| max.min(depth)));
+    }
+
+    // A glob without any wildcards may point to a missing
+    // directory, in which case there are simply no matches
+    let walker = if fs::metadata(&base).await.is_ok_and(|meta| meta.is_dir()) {
+        start_walker(base, options, Some(matcher))?
+    } else {
+        let (_, rx) = bounded(1);
+        DirWalker { entries: rx }
+    };
+
+    Ok(walker)
+}
+
+async fn ensure_dir_exists(dir: &Path) -> LuaResult<()> {
+    match fs::metadata(dir).await {
+        Ok(meta) if meta.is_dir() => Ok(()),
+        Ok(_) => Err(LuaError::RuntimeError(format!(
+            "The given path '{}' is not a directory",
+            dir.display()
+        ))),
+        Err(_) => Err(LuaError::RuntimeError(format!(
+            "No directory exists at the path '{}'",
+            dir.display()
+        ))),
+    }
+}
+
+fn start_walker(
+    root: PathBuf,
+    options: FsWalkOptions,
+    matcher: Option<GlobMatcher>,
+) -> LuaResult<DirWalker> {
+    let mut overrides = OverrideBuilder::new(&root);
+    for pattern in &options.exclude {
+        // NOTE: Override globs are inverted compared to
+        // gitignore files, a leading "!" excludes the path
+        overrides.add(&format!("!{pattern}")).map_err(|e| {
+            LuaError::RuntimeError(format!("Invalid exclude pattern '{pattern}'\n{e}"))
+        })?;
+    }
+    let overrides = overrides.build().into_lua_err()?;
+
+    let mut builder = WalkBuilder::new(&root);
+    builder
+        .standard_filters(false)
+        .hidden(!options.include_hidden)
+        .follow_links(options.follow_symlinks)
+        .max_depth(options.max_depth)
+        .overrides(overrides)
+        .sort_by_file_name(Ord::cmp);
+
+    let (tx, rx) = bounded(CHANNEL_CAPACITY);
+    blocking::unblock(move || run_walker(&builder, &root, matcher.as_ref(), &tx)).detach();
+
+    Ok(DirWalker { entries: rx })
+}
+
+fn run_walker(
+    builder: &WalkBuilder,
+    root: &Path,
+    matcher: Option<&GlobMatcher>,
+    tx: &Sender<Result<WalkEntry, String>>,
+) {
+    for result in builder.build() {
+        let item = match result {
+            // The root itself is never included, same as fs.readDir
+            Ok(entry) if entry.depth() == 0 => continue,
+            Ok(entry) => {
+                let path = display_path(root, &entry);
+                if matcher.is_some_and(|matcher| !matcher.is_match(&path)) {
+                    continue;
+                }
+                entry
+                    .metadata()
+                    .map(|meta| WalkEntry {
+                        path,
+                        depth: entry.depth(),
+                        metadata: FsMetadata::from(meta),
+                    })
+                    .map_err(|e| e.to_string())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        // NOTE: Sending will only fail if the walker was garbage
+        // collected, in which case nobody is reading entries anymore
+        if tx.send_blocking(item).is_err() {
+            break;
+        }
+    }
+}
+
+fn display_path(root: &Path, entry: &DirEntry) -> PathBuf {
+    // Walking from the implicit current directory adds a
+    // leading "./" to all paths, which globs should not need
+    if root == Path::new(".") {
+        entry
+            .path()
+            .strip_prefix(root)
+            .map_or_else(|_| entry.path().to_path_buf(), Path::to_path_buf)
+    } else {
+        entry.path().to_path_buf()
+    }
+}
+
+/**
+    Splits a glob pattern into the directory containing the first
+    component with a wildcard, and all components from there on.
+*/
+fn split_glob_pattern(pattern: &str) -> (PathBuf, Vec<String>) {
+    let mut base = PathBuf::new();
+    let mut rest = Vec::new();
+    for component in Path::new(pattern).components() {
+        let component_str = component.as_os_str().to_string_lossy();
+        if rest.is_empty() && !component_str.contains(GLOB_META_CHARS) {
+            base.push(component);
+        } else if !matches!(component, Component::CurDir) {
+            rest.push(component_str.into_owned());
+        }
+    }
+    // A pattern without any wildcards should match just the path itself
+    if rest.is_empty()
+        && let Some(name) = base.file_name()
+    {
+        rest.push(name.to_string_lossy().into_owned());
+        base.pop();
+    }
+    if base.as_os_str().is_empty() {
+        base.push(".");
+    }
+    (base, rest)
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use mlua::prelude::*;
+
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    pub recursive: bool,
+    pub debounce: Duration,
+    pub handle_event: Option<LuaFunction>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            recursive: false,
+            debounce: DEFAULT_DEBOUNCE,
+            handle_event: None,
+        }
+    }
+}
+
+impl FromLua for WatchConfig {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        match &value {
+            // Nothing = iterator-style watcher, using default options
+            LuaValue::Nil => Ok(Self::default()),
+            // Single function = event handler, rest is default
+            LuaValue::Function(f) => Ok(Self {
+                handle_event: Some(f.clone()),
+                ..Self::default()
+            }),
+            // Table means custom options
+            LuaValue::Table(t) => {
+                let recursive: Option<bool> = t.get("recursive")?;
+                let debounce: Option<f64> = t.get("debounce")?;
+                let handle_event: Option<LuaFunction> = t.get("handleEvent")?;
+
+                let debounce = match debounce {
+                    None => DEFAULT_DEBOUNCE,
+                    Some(secs) => Duration::try_from_secs_f64(secs).map_err(|_| {
+                        LuaError::FromLuaConversionError {
+                            from: value.type_name(),
+                            to: "WatchConfig".to_string(),
+                            message: Some(format!(
+                                "Invalid debounce - expected a non-negative number of seconds, got {secs}"
+                            )),
+                        }
+                    })?,
+                };
+
+                Ok(Self {
+                    recursive: recursive.unwrap_or(false),
+                    debounce,
+                    handle_event,
+                })
+            }
+            // Anything else is invalid
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "WatchConfig".to_string(),
+                message: Some(format!(
+                    "Invalid watch options - expected function or table, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
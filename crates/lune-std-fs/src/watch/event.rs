@@ -0,0 +1,108 @@
+use std::{fmt, path::PathBuf};
+
+use mlua::prelude::*;
+use notify::{
+    Event as NotifyEvent, EventKind as NotifyEventKind,
+    event::{ModifyKind, RenameMode},
+};
+
+use lune_utils::TableBuilder;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEventKind {
+    Create,
+    Modify,
+    Remove,
+    Rename,
+}
+
+impl fmt::Display for WatchEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Create => "create",
+                Self::Modify => "modify",
+                Self::Remove => "remove",
+                Self::Rename => "rename",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchEvent {
+    pub(crate) kind: WatchEventKind,
+    pub(crate) paths: Vec<PathBuf>,
+    // Used to pair up the separate halves of a rename with the combined event
+    tracker: Option<(usize, RenameMode)>,
+}
+
+impl WatchEvent {
+    /**
+        Converts a raw `notify` event into a watch event.
+
+        Returns `None` for events that are not relevant to scripts, such as access events.
+    */
+    pub fn from_notify(event: NotifyEvent) -> Option<Self> {
+        let (kind, rename_mode) = match event.kind {
+            NotifyEventKind::Create(_) => (WatchEventKind::Create, None),
+            NotifyEventKind::Remove(_) => (WatchEventKind::Remove, None),
+            NotifyEventKind::Modify(ModifyKind::Name(mode)) => (WatchEventKind::Rename, Some(mode)),
+            NotifyEventKind::Modify(_) => (WatchEventKind::Modify, None),
+            NotifyEventKind::Any | NotifyEventKind::Access(_) | NotifyEventKind::Other => {
+                return None;
+            }
+        };
+        let tracker = event.tracker();
+        Some(Self {
+            kind,
+            paths: event.paths,
+            tracker: tracker.zip(rename_mode),
+        })
+    }
+}
+
+/**
+    Coalesces a batch of watch events, removing exact duplicates as
+    well as the separate halves of any renames that were also reported
+    as a single event containing both the old and new paths.
+*/
+pub fn coalesce_events(events: Vec<WatchEvent>) -> Vec<WatchEvent> {
+    let full_renames = events
+        .iter()
+        .filter_map(|event| match event.tracker {
+            Some((tracker, RenameMode::Both)) => Some(tracker),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut coalesced: Vec<WatchEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        let is_partial_rename = matches!(
+            event.tracker,
+            Some((tracker, RenameMode::From | RenameMode::To))
+                if full_renames.contains(&tracker)
+        );
+        if !is_partial_rename && !coalesced.contains(&event) {
+            coalesced.push(event);
+        }
+    }
+    coalesced
+}
+
+impl IntoLua for WatchEvent {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let paths = self
+            .paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        TableBuilder::new(lua.clone())?
+            .with_value("kind", self.kind.to_string())?
+            .with_value("paths", paths)?
+            .build_readonly()
+            .map(LuaValue::Table)
+    }
+}
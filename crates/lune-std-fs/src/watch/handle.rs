@@ -0,0 +1,56 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use async_channel::{Receiver, Sender, unbounded};
+use mlua::prelude::*;
+
+use super::event::WatchEvent;
+
+#[derive(Debug, Clone)]
+pub struct WatchHandle {
+    shutdown: Arc<AtomicBool>,
+    sender: Sender<()>,
+    events: Option<Receiver<WatchEvent>>,
+}
+
+impl WatchHandle {
+    pub fn new(events: Option<Receiver<WatchEvent>>) -> (Self, Receiver<()>) {
+        let (sender, receiver) = unbounded();
+        let this = Self {
+            shutdown: Arc::new(AtomicBool::new(false)),
+            sender,
+            events,
+        };
+        (this, receiver)
+    }
+}
+
+impl LuaUserData for WatchHandle {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("stop", |_, this, ()| {
+            if this.shutdown.load(Ordering::SeqCst) {
+                Err(LuaError::runtime("Watcher already stopped"))
+            } else {
+                this.shutdown.store(true, Ordering::SeqCst);
+                this.sender.try_send(()).ok();
+                this.sender.close();
+                Ok(())
+            }
+        });
+        methods.add_async_method("next", |_, this, ()| {
+            let events = this.events.clone();
+            async move {
+                let Some(events) = events else {
+                    return Err(LuaError::runtime(
+                        "Watcher was created with an event handler, events can not be read using next",
+                    ));
+                };
+                // NOTE: The receiver will only error once the watcher
+                // has stopped and all of the queued events were read
+                Ok(events.recv().await.ok())
+            }
+        });
+    }
+}
@@ -0,0 +1,159 @@
+use std::{path::PathBuf, time::Duration};
+
+use async_channel::{Receiver, unbounded};
+use async_io::Timer;
+use futures_lite::prelude::*;
+use notify::{Event as NotifyEvent, RecursiveMode, Result as NotifyResult, Watcher};
+
+use mlua::prelude::*;
+use mlua_luau_scheduler::{LuaSchedulerExt, LuaSpawnExt};
+
+use self::{
+    config::WatchConfig,
+    event::{WatchEvent, coalesce_events},
+    handle::WatchHandle,
+};
+
+pub mod config;
+pub mod event;
+pub mod handle;
+
+enum Next {
+    Shutdown(bool),
+    Event(Option<NotifyEvent>),
+    Quiet,
+}
+
+/**
+    Starts watching the given path for changes using the given configuration.
+
+    Returns a `WatchHandle` that can be used to stop watching, and to
+    read events one by one if no event handler function was given.
+*/
+pub fn watch(lua: Lua, path: PathBuf, config: WatchConfig) -> LuaResult<WatchHandle> {
+    // NOTE: The notify callback is called on a separate
+    // thread, so we need to send events over to our own
+    let (notify_tx, notify_rx) = unbounded();
+    let mut watcher = notify::recommended_watcher(move |res: NotifyResult<NotifyEvent>| {
+        // FUTURE: Propagate watch errors somehow?
+        if let Ok(event) = res {
+            notify_tx.send_blocking(event).ok();
+        }
+    })
+    .into_lua_err()?;
+
+    let mode = if config.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher.watch(&path, mode).map_err(|e| {
+        LuaError::RuntimeError(format!("Failed to watch path '{}'\n{e}", path.display()))
+    })?;
+
+    let (events_tx, events_rx) = if config.handle_event.is_some() {
+        (None, None)
+    } else {
+        let (tx, rx) = unbounded();
+        (Some(tx), Some(rx))
+    };
+    let (handle, shutdown_rx) = WatchHandle::new(events_rx);
+
+    lua.spawn_local({
+        let lua = lua.clone();
+        async move {
+            // NOTE: The watcher stops watching once it is dropped,
+            // so we must keep it alive for as long as this task runs
+            let _watcher = watcher;
+            let mut handle_dropped = false;
+            loop {
+                // 1. Wait for the next event, or until we should stop
+                let first = match next_event(&notify_rx, &shutdown_rx, handle_dropped, None).await {
+                    Next::Shutdown(false) if events_tx.is_none() => {
+                        // NOTE: We will only get here if the watch handle is dropped, this means
+                        // lua has garbage collected it and the user does not want to manually
+                        // stop the watcher using the handle. Run forever, same as net.serve
+                        // - unless events are read using the handle, which nobody can do now
+                        handle_dropped = true;
+                        continue;
+                    }
+                    Next::Event(Some(event)) => event,
+                    Next::Shutdown(_) | Next::Event(None) | Next::Quiet => break,
+                };
+
+                // 2. Keep collecting events until none have arrived for the debounce duration
+                let mut batch = WatchEvent::from_notify(first)
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                let mut stopped = false;
+                loop {
+                    while let Ok(event) = notify_rx.try_recv() {
+                        batch.extend(WatchEvent::from_notify(event));
+                    }
+                    if config.debounce.is_zero() {
+                        break;
+                    }
+                    match next_event(
+                        &notify_rx,
+                        &shutdown_rx,
+                        handle_dropped,
+                        Some(config.debounce),
+                    )
+                    .await
+                    {
+                        Next::Shutdown(false) if events_tx.is_none() => handle_dropped = true,
+                        Next::Shutdown(_) => {
+                            stopped = true;
+                            break;
+                        }
+                        Next::Event(Some(event)) => batch.extend(WatchEvent::from_notify(event)),
+                        Next::Event(None) | Next::Quiet => break,
+                    }
+                }
+                if stopped {
+                    break;
+                }
+
+                // 3. Deliver the coalesced events, either to the handler or the handle
+                for event in coalesce_events(batch) {
+                    if let Some(handler) = &config.handle_event {
+                        if let Err(_err) = lua.push_thread_back(handler.clone(), event) {
+                            // TODO: Propagate the error somehow?
+                        }
+                    } else if let Some(tx) = &events_tx
+                        && tx.send(event).await.is_err()
+                    {
+                        // NOTE: The handle, and its receiver, has been garbage
+                        // collected - nobody can read events anymore, so stop
+                        return;
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(handle)
+}
+
+async fn next_event(
+    notify_rx: &Receiver<NotifyEvent>,
+    shutdown_rx: &Receiver<()>,
+    handle_dropped: bool,
+    timeout: Option<Duration>,
+) -> Next {
+    let shutdown = async {
+        if handle_dropped {
+            std::future::pending::<()>().await;
+        }
+        Next::Shutdown(shutdown_rx.recv().await.is_ok())
+    };
+    let event = async { Next::Event(notify_rx.recv().await.ok()) };
+    let quiet = async {
+        match timeout {
+            Some(duration) => Timer::after(duration).await,
+            None => std::future::pending().await,
+        };
+        Next::Quiet
+    };
+    shutdown.or(event).or(quiet).await
+}
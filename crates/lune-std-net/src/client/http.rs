@@ -0,0 +1,128 @@
+use http_body_util::Full;
+use hyper::{
+    Request as HyperRequest, Response as HyperResponse, Version,
+    body::{Bytes, Incoming},
+    client::conn::{http1, http2},
+};
+
+use mlua::prelude::*;
+use url::Url;
+
+use crate::{
+    client::{rustls::ALPN_H2, stream::HttpStream, tls::ClientTlsConfig},
+    shared::hyper::{HyperConnExecutor, HyperExecutor, HyperIo},
+};
+
+/**
+    A connection to an HTTP server, using whichever
+    HTTP version was negotiated when connecting.
+*/
+#[derive(Debug)]
+pub enum HttpSender {
+    Http1(http1::SendRequest<Full<Bytes>>),
+    Http2(http2::SendRequest<Full<Bytes>>),
+}
+
+impl HttpSender {
+    /**
+        Connects to the server at the given URL.
+
+        The `http2` preference works as follows:
+
+        - `None` negotiates HTTP/2 using ALPN for TLS connections, and uses HTTP/1.1 otherwise
+        - `Some(true)` requires HTTP/2, using prior knowledge (h2c) for connections without TLS
+        - `Some(false)` always uses HTTP/1.1
+    */
+    pub async fn connect(
+        lua: &Lua,
+        url: &Url,
+        http2: Option<bool>,
+        tls: &ClientTlsConfig,
+    ) -> LuaResult<Self> {
+        let config = if http2 == Some(false) {
+            tls.config()
+        } else {
+            tls.http_config()
+        };
+        let stream = HttpStream::connect_url_with_config(url.clone(), config).await?;
+
+        let is_tls = matches!(url.scheme(), "https" | "wss");
+        let use_http2 = if is_tls {
+            stream.alpn_protocol() == Some(ALPN_H2)
+        } else {
+            http2 == Some(true)
+        };
+        if http2 == Some(true) && !use_http2 {
+            return Err(LuaError::RuntimeError(format!(
+                "Server at '{}' does not support HTTP/2",
+                url.origin().ascii_serialization()
+            )));
+        }
+
+        let io = HyperIo::from(stream);
+        if use_http2 {
+            let exec = HyperConnExecutor::default();
+            let (sender, conn) = http2::handshake(exec.clone(), io).await.into_lua_err()?;
+            HyperExecutor::execute(lua.clone(), async move { exec.run(conn).await });
+            Ok(Self::Http2(sender))
+        } else {
+            let (sender, conn) = http1::handshake(io).await.into_lua_err()?;
+            HyperExecutor::execute(lua.clone(), conn);
+            Ok(Self::Http1(sender))
+        }
+    }
+
+    /**
+        Returns the HTTP version used by this connection.
+    */
+    pub fn version(&self) -> Version {
+        match self {
+            Self::Http1(_) => Version::HTTP_11,
+            Self::Http2(_) => Version::HTTP_2,
+        }
+    }
+
+    /**
+        Returns `true` if the connection has been closed, and can no longer be used.
+    */
+    pub fn is_closed(&self) -> bool {
+        match self {
+            Self::Http1(sender) => sender.is_closed(),
+            Self::Http2(sender) => sender.is_closed(),
+        }
+    }
+
+    /**
+        Waits until the connection is ready to send another request.
+    */
+    pub async fn ready(&mut self) -> hyper::Result<()> {
+        match self {
+            Self::Http1(sender) => sender.ready().await,
+            Self::Http2(sender) => sender.ready().await,
+        }
+    }
+
+    /**
+        Creates another handle to the same connection, if it can send multiple
+        requests at the same time, which is only the case when using HTTP/2.
+    */
+    pub fn try_share(&self) -> Option<Self> {
+        match self {
+            Self::Http1(_) => None,
+            Self::Http2(sender) => Some(Self::Http2(sender.clone())),
+        }
+    }
+
+    /**
+        Sends a request on this connection, returning the response.
+    */
+    pub async fn send_request(
+        &mut self,
+        request: HyperRequest<Full<Bytes>>,
+    ) -> hyper::Result<HyperResponse<Incoming>> {
+        match self {
+            Self::Http1(sender) => sender.send_request(request).await,
+            Self::Http2(sender) => sender.send_request(request).await,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use mlua::prelude::*;
+
+use crate::{
+    client::{
+        pool::{ConnectionPool, PoolConfig},
+        send,
+    },
+    shared::{request::Request, response::Response},
+};
+
+/**
+    An HTTP client with its own pool of connections, created using `net.http.client`.
+*/
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    pool: ConnectionPool,
+}
+
+impl HttpClient {
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            pool: ConnectionPool::new(config),
+        }
+    }
+
+    /**
+        Gets the default client for the given Lua state, which
+        is shared by all requests sent using `net.request`.
+    */
+    pub fn default_for(lua: &Lua) -> Self {
+        if let Some(client) = lua.app_data_ref::<Self>() {
+            return client.clone();
+        }
+        let client = Self::new(PoolConfig::default());
+        lua.set_app_data(client.clone());
+        client
+    }
+
+    pub async fn request(&self, lua: Lua, request: Request) -> LuaResult<Response> {
+        send(request, lua, self.pool.clone()).await
+    }
+}
+
+impl LuaUserData for HttpClient {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_async_method("request", |lua, this, request: Request| {
+            let this = this.clone();
+            async move { this.request(lua, request).await }
+        });
+    }
+}
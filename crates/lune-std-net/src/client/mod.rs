@@ -8,13 +8,19 @@ use crate::{
     client::{
         stream::{MaybeTlsStream, WsStream},
         tcp::TcpConfig,
+        ws::WsConfig,
     },
     shared::{request::Request, tcp::Tcp, websocket::Websocket},
 };
 
+pub mod http;
+pub mod http_client;
+pub mod pool;
 pub mod rustls;
 pub mod stream;
 pub mod tcp;
+pub mod tls;
+pub mod ws;
 
 mod fetch;
 mod send;
@@ -22,13 +28,11 @@ mod send;
 pub use self::fetch::fetch;
 pub use self::send::send;
 
-const MAX_REDIRECTS: usize = 10;
-
 /**
-    Connects to a websocket at the given URL.
+    Connects to a websocket at the given URL, using the given config.
 */
-pub async fn connect_ws(url: Url) -> LuaResult<Websocket<WsStream>> {
-    let stream = WsStream::connect_url(url).await?;
+pub async fn connect_ws(url: Url, config: WsConfig) -> LuaResult<Websocket<WsStream>> {
+    let stream = WsStream::connect_url(url, config.tls.config()).await?;
     Ok(Websocket::from(stream))
 }
 
@@ -36,9 +40,9 @@ pub async fn connect_ws(url: Url) -> LuaResult<Websocket<WsStream>> {
     Connects using plain TCP using the given host, port, and config.
 */
 pub async fn connect_tcp(host: String, port: u16, config: TcpConfig) -> LuaResult<Tcp> {
-    let tls = config.tls.unwrap_or_default();
+    let tls = config.tls.map(|tls| tls.config());
 
-    let stream = MaybeTlsStream::connect(&host, port, tls)
+    let stream = MaybeTlsStream::connect_with_config(&host, port, tls)
         .await
         .into_lua_err()?;
 
@@ -55,7 +59,7 @@ fn try_follow_redirect(
     response: &HyperResponse<Incoming>,
 ) -> Result<bool, &'static str> {
     if let Some((new_method, new_uri)) = check_redirect(request.inner.method().clone(), response) {
-        if request.redirects.is_some_and(|r| r >= MAX_REDIRECTS) {
+        if request.redirects.unwrap_or_default() >= request.max_redirects {
             return Err("Too many redirects");
         }
 
@@ -0,0 +1,282 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use async_io::Timer;
+
+use mlua::prelude::*;
+use mlua_luau_scheduler::LuaSpawnExt;
+use url::Url;
+
+use crate::client::{http::HttpSender, tls::ClientTlsConfig};
+
+const DEFAULT_MAX_IDLE_PER_HOST: usize = 32;
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/**
+    Configuration for a [`ConnectionPool`].
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct PoolConfig {
+    pub max_idle_per_host: usize,
+    pub idle_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: DEFAULT_MAX_IDLE_PER_HOST,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+        }
+    }
+}
+
+impl FromLua for PoolConfig {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let tab = match value {
+            LuaValue::Nil => return Ok(Self::default()),
+            LuaValue::Table(tab) => tab,
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "PoolConfig".to_string(),
+                    message: Some(format!(
+                        "Invalid client config - expected table or nil, got {}",
+                        value.type_name()
+                    )),
+                });
+            }
+        };
+
+        let mut this = Self::default();
+        if let Some(max) = tab.get::<Option<LuaNumber>>("maxIdlePerHost")? {
+            if max < 0.0 || max.fract() != 0.0 || !max.is_finite() {
+                return Err(LuaError::RuntimeError(format!(
+                    "Invalid value for option 'maxIdlePerHost' - expected a non-negative integer, got {max}"
+                )));
+            }
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            {
+                this.max_idle_per_host = max as usize;
+            }
+        }
+        if let Some(secs) = tab.get::<Option<LuaNumber>>("idleTimeout")? {
+            this.idle_timeout = Duration::try_from_secs_f64(secs).map_err(|_| {
+                LuaError::RuntimeError(format!(
+                    "Invalid value for option 'idleTimeout' - expected a non-negative number of seconds, got {secs}"
+                ))
+            })?;
+        }
+
+        Ok(this)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PoolKey {
+    scheme: String,
+    host: String,
+    port: u16,
+    http2: Option<bool>,
+    tls: ClientTlsConfig,
+}
+
+impl PoolKey {
+    fn new(url: &Url, http2: Option<bool>, tls: &ClientTlsConfig) -> LuaResult<Self> {
+        let host = url
+            .host_str()
+            .ok_or_else(|| LuaError::runtime("Invalid URL - unknown or missing host"))?;
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| LuaError::runtime("Invalid URL - unknown or missing port"))?;
+        Ok(Self {
+            scheme: url.scheme().to_string(),
+            host: host.to_string(),
+            port,
+            http2,
+            tls: tls.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct IdleConnection {
+    sender: HttpSender,
+    idle_since: Instant,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    idle: HashMap<PoolKey, Vec<IdleConnection>>,
+    reaping: bool,
+}
+
+/**
+    A connection taken from a [`ConnectionPool`].
+
+    Should be given back using [`ConnectionPool::release`] once its response
+    has been fully received, so that it may be reused for other requests.
+*/
+#[derive(Debug)]
+pub struct PooledConnection {
+    key: PoolKey,
+    pub sender: HttpSender,
+    pub reused: bool,
+}
+
+/**
+    A pool of connections to HTTP servers, which keeps connections
+    alive after their requests finish, so that they can be reused.
+
+    HTTP/1.1 connections may only send one request at a time, and are taken
+    out of the pool while in use, while HTTP/2 connections are shared
+    between all requests to the same server, and always stay in the pool.
+
+    Idle connections never keep the Lua scheduler running.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionPool {
+    config: PoolConfig,
+    state: Arc<Mutex<PoolState>>,
+}
+
+impl ConnectionPool {
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            config,
+            state: Arc::default(),
+        }
+    }
+
+    /**
+        Gets a connection to the server at the given URL, reusing an
+        idle connection from the pool if possible, or connecting if not.
+    */
+    pub async fn connect(
+        &self,
+        lua: &Lua,
+        url: &Url,
+        http2: Option<bool>,
+        tls: &ClientTlsConfig,
+    ) -> LuaResult<PooledConnection> {
+        let key = PoolKey::new(url, http2, tls)?;
+
+        while let Some(mut sender) = self.take_idle(&key) {
+            if sender.ready().await.is_ok() {
+                return Ok(PooledConnection {
+                    key,
+                    sender,
+                    reused: true,
+                });
+            }
+        }
+
+        let sender = HttpSender::connect(lua, url, http2, tls).await?;
+        if let Some(shared) = sender.try_share() {
+            self.put_idle(lua, key.clone(), shared);
+        }
+
+        Ok(PooledConnection {
+            key,
+            sender,
+            reused: false,
+        })
+    }
+
+    /**
+        Gives a connection back to the pool, after its response has been fully received.
+    */
+    pub fn release(&self, lua: &Lua, conn: PooledConnection) {
+        // NOTE: Shared connections were never taken out of the pool
+        if conn.sender.try_share().is_none() {
+            self.put_idle(lua, conn.key, conn.sender);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn take_idle(&self, key: &PoolKey) -> Option<HttpSender> {
+        let now = Instant::now();
+        let timeout = self.config.idle_timeout;
+
+        let mut state = self.lock();
+        let list = state.idle.get_mut(key)?;
+        list.retain(|conn| !conn.sender.is_closed() && now - conn.idle_since < timeout);
+
+        // Prefer the most recently used connection, since it is the least likely to have been closed
+        let sender = match list.last_mut() {
+            Some(conn) => match conn.sender.try_share() {
+                Some(shared) => {
+                    conn.idle_since = now;
+                    Some(shared)
+                }
+                None => list.pop().map(|conn| conn.sender),
+            },
+            None => None,
+        };
+
+        if list.is_empty() {
+            state.idle.remove(key);
+        }
+        sender
+    }
+
+    fn put_idle(&self, lua: &Lua, key: PoolKey, sender: HttpSender) {
+        if sender.is_closed()
+            || self.config.max_idle_per_host == 0
+            || self.config.idle_timeout.is_zero()
+        {
+            return;
+        }
+
+        let mut state = self.lock();
+        let list = state.idle.entry(key).or_default();
+        if list.len() >= self.config.max_idle_per_host {
+            return;
+        }
+        list.push(IdleConnection {
+            sender,
+            idle_since: Instant::now(),
+        });
+
+        if !state.reaping {
+            state.reaping = true;
+            lua.spawn(self.clone().reap()).detach();
+        }
+    }
+
+    /**
+        Closes idle connections as they time out, until there are none left.
+    */
+    async fn reap(self) {
+        loop {
+            let deadline = {
+                let now = Instant::now();
+                let timeout = self.config.idle_timeout;
+
+                let mut state = self.lock();
+                state.idle.retain(|_, list| {
+                    list.retain(|conn| !conn.sender.is_closed() && now - conn.idle_since < timeout);
+                    !list.is_empty()
+                });
+
+                let oldest = state
+                    .idle
+                    .values()
+                    .flatten()
+                    .map(|conn| conn.idle_since)
+                    .min();
+                let Some(oldest) = oldest else {
+                    state.reaping = false;
+                    return;
+                };
+                oldest + timeout
+            };
+            Timer::at(deadline).await;
+        }
+    }
+}
@@ -5,6 +5,9 @@ use std::sync::{
 
 use rustls::{ClientConfig, crypto::ring};
 
+pub const ALPN_H2: &[u8] = b"h2";
+pub const ALPN_HTTP1: &[u8] = b"http/1.1";
+
 static PROVIDER_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
 pub fn initialize_provider() {
@@ -24,3 +27,15 @@ pub static CLIENT_CONFIG: LazyLock<Arc<ClientConfig>> = LazyLock::new(|| {
         .with_no_client_auth()
         .into()
 });
+
+/**
+    Client config for HTTP requests, which also negotiates HTTP/2 using ALPN.
+
+    This must not be used for other kinds of connections, such as web sockets, since
+    the server may then pick HTTP/2 even though the connection will not be using it.
+*/
+pub static HTTP_CLIENT_CONFIG: LazyLock<Arc<ClientConfig>> = LazyLock::new(|| {
+    let mut config = ClientConfig::clone(&CLIENT_CONFIG);
+    config.alpn_protocols = vec![ALPN_H2.to_vec(), ALPN_HTTP1.to_vec()];
+    config.into()
+});
@@ -1,37 +1,51 @@
-use http_body_util::Full;
+use std::time::Duration;
+
+use async_io::Timer;
+use http_body_util::{BodyExt, Full};
 use hyper::{
-    Method, Request as HyperRequest,
-    client::conn::http1::handshake,
+    Method, Request as HyperRequest, StatusCode, Version,
     header::{ACCEPT, CONTENT_LENGTH, HOST, HeaderValue, USER_AGENT},
 };
 
 use mlua::prelude::*;
+use mlua_luau_scheduler::{LuaSchedulerExt, LuaSpawnExt, ThreadId};
 use url::Url;
 
 use crate::{
-    client::stream::HttpStream,
+    client::pool::ConnectionPool,
     shared::{
+        futures::{Either, either},
         headers::create_user_agent_header,
-        hyper::{HyperExecutor, HyperIo},
         request::Request,
         response::Response,
     },
 };
 
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(8);
+
+/**
+    The outcome of a single attempt at sending a request.
+*/
+enum Attempt {
+    /// The server redirected the request, which was updated to follow it.
+    Redirected,
+    /// The full response was received.
+    Completed(Response),
+    /// The request failed, but may succeed if it is sent again.
+    Failed(LuaError),
+}
+
 /**
     Sends the request and returns the final response.
 
     This will follow any redirects returned by the server,
     modifying the request method and body as necessary.
-*/
-pub async fn send(mut request: Request, lua: Lua) -> LuaResult<Response> {
-    let mut url = request
-        .inner
-        .uri()
-        .to_string()
-        .parse::<Url>()
-        .into_lua_err()?;
 
+    Requests are stopped as soon as the calling Lua thread
+    is cancelled, closing the connection they were using.
+*/
+pub async fn send(mut request: Request, lua: Lua, pool: ConnectionPool) -> LuaResult<Response> {
     // Some headers are required by most if not
     // all servers, make sure those are present...
     if !request.headers().contains_key(USER_AGENT.as_str()) {
@@ -49,29 +63,149 @@ pub async fn send(mut request: Request, lua: Lua) -> LuaResult<Response> {
         request.inner.headers_mut().insert(ACCEPT, accept);
     }
 
-    // ... we can now safely continue and send the request
+    // ... we can now safely continue and send the request - note that cancelled
+    // threads are never resumed, and stop polling this future, so the request
+    // runs in its own task that gets dropped as soon as the thread is cancelled
+    let cancelled = lua.wait_for_cancel(ThreadId::from(&lua.current_thread()));
+    let (tx, rx) = async_channel::bounded(1);
+    lua.spawn_local({
+        let lua = lua.clone();
+        async move {
+            let fut = Box::pin(send_inner(request, lua, pool));
+            if let Either::Left(result) = either(fut, cancelled).await {
+                tx.try_send(result).ok();
+            }
+        }
+    });
+
+    rx.recv()
+        .await
+        .map_err(|_| LuaError::runtime("Request was cancelled"))?
+}
+
+async fn send_inner(request: Request, lua: Lua, pool: ConnectionPool) -> LuaResult<Response> {
+    // NOTE: The timeout is for the request as a whole, and
+    // includes all redirects, retries, and delays between them
+    let Some(timeout) = request.timeout else {
+        return send_with_retries(request, lua, pool).await;
+    };
+    let fut = Box::pin(send_with_retries(request, lua, pool));
+    match either(fut, Timer::after(timeout)).await {
+        Either::Left(result) => result,
+        Either::Right(_) => Err(LuaError::RuntimeError(format!(
+            "Request timed out after {}s",
+            timeout.as_secs_f64()
+        ))),
+    }
+}
+
+async fn send_with_retries(
+    mut request: Request,
+    lua: Lua,
+    pool: ConnectionPool,
+) -> LuaResult<Response> {
+    let mut url = request
+        .inner
+        .uri()
+        .to_string()
+        .parse::<Url>()
+        .into_lua_err()?;
+
+    let mut retries = 0;
+    let mut retry_delay = RETRY_BASE_DELAY;
     loop {
-        let stream = HttpStream::connect_url(url.clone()).await?;
+        let attempt = send_attempt(&lua, &pool, &mut url, &mut request).await?;
+
+        // Only idempotent requests are safe to send again, since
+        // others may have already been processed by the server
+        let can_retry = retries < request.retries && request.method().is_idempotent();
+        match attempt {
+            Attempt::Redirected => continue,
+            Attempt::Completed(response)
+                if !can_retry || !is_retryable_status(response.inner.status()) =>
+            {
+                break Ok(response);
+            }
+            Attempt::Failed(e) if !can_retry => break Err(e),
+            Attempt::Completed(_) | Attempt::Failed(_) => {}
+        }
 
-        let (mut sender, conn) = handshake(HyperIo::from(stream)).await.into_lua_err()?;
+        Timer::after(retry_delay).await;
+        retry_delay = (retry_delay * 2).min(RETRY_MAX_DELAY);
+        retries += 1;
+    }
+}
 
-        HyperExecutor::execute(lua.clone(), conn);
+async fn send_attempt(
+    lua: &Lua,
+    pool: &ConnectionPool,
+    url: &mut Url,
+    request: &mut Request,
+) -> LuaResult<Attempt> {
+    loop {
+        let connect = pool.connect(lua, url, request.http2, &request.tls);
+        let connected = match request.connect_timeout {
+            None => connect.await,
+            Some(timeout) => match either(connect, Timer::after(timeout)).await {
+                Either::Left(connected) => connected,
+                Either::Right(_) => Err(LuaError::RuntimeError(format!(
+                    "Connection to '{}' timed out after {}s",
+                    url.origin().ascii_serialization(),
+                    timeout.as_secs_f64()
+                ))),
+            },
+        };
+        let mut conn = match connected {
+            Ok(conn) => conn,
+            Err(e) => return Ok(Attempt::Failed(e)),
+        };
 
         let (mut parts, body) = request.clone_inner().into_parts();
-        if let Some(host) = parts.uri.host() {
+        if conn.sender.version() == Version::HTTP_2 {
+            // NOTE: HTTP/2 sends the host as part of the full URI instead of
+            // as a header, and the URI may be relative after a redirect
+            parts.uri = url.as_str().parse().into_lua_err()?;
+            parts.version = Version::HTTP_2;
+        } else if let Some(host) = parts.uri.host() {
             let host = HeaderValue::from_str(host).unwrap();
             parts.headers.insert(HOST, host);
         }
 
         let data = HyperRequest::from_parts(parts, Full::new(body.into_bytes()));
-        let incoming = sender.send_request(data).await.into_lua_err()?;
+        let incoming = match conn.sender.send_request(data).await {
+            Ok(incoming) => incoming,
+            // NOTE: Idle connections may be closed by the server at any time, in
+            // which case the request was never processed, and is safe to try again
+            Err(e) if conn.reused && e.is_canceled() => continue,
+            Err(e) => return Ok(Attempt::Failed(e.into_lua_err())),
+        };
 
-        if super::try_follow_redirect(&mut url, &mut request, &incoming)
-            .map_err(LuaError::external)?
-        {
-            continue;
+        if super::try_follow_redirect(url, request, &incoming).map_err(LuaError::external)? {
+            // NOTE: The connection may only be reused for the next
+            // request once the redirect response has been fully read
+            if incoming.into_body().collect().await.is_ok() {
+                pool.release(lua, conn);
+            }
+            return Ok(Attempt::Redirected);
         }
 
-        break Response::from_incoming(incoming, request.decompress).await;
+        return match Response::from_incoming(incoming, request.decompress).await {
+            Ok(response) => {
+                pool.release(lua, conn);
+                Ok(Attempt::Completed(response))
+            }
+            Err(e) => Ok(Attempt::Failed(e)),
+        };
     }
 }
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
@@ -14,6 +14,7 @@ use async_tungstenite::{
 use futures::Sink;
 use futures_lite::prelude::*;
 use futures_rustls::{TlsConnector, TlsStream};
+use rustls::ClientConfig;
 use rustls_pki_types::ServerName;
 use url::Url;
 
@@ -40,19 +41,23 @@ pub enum MaybeTlsStream {
 
 impl MaybeTlsStream {
     /**
-        Connects to a host and port, additionally using TLS if specified.
+        Connects to a host and port, additionally using TLS with the given config, if any.
 
         Using this constructor is likely unergonomic - prefer using
         [`MaybeTlsStream::connect_url`] instead, if possible.
 
         The given `host` must be a valid DNS name, when using TLS.
     */
-    pub async fn connect(host: &str, port: u16, tls: bool) -> Result<Self> {
+    pub async fn connect_with_config(
+        host: &str,
+        port: u16,
+        tls: Option<Arc<ClientConfig>>,
+    ) -> Result<Self> {
         let stream = TcpStream::connect((host, port)).await?;
 
-        let stream = if tls {
+        let stream = if let Some(config) = tls {
             let servname = ServerName::try_from(host).map_err(Error::other)?.to_owned();
-            let connector = TlsConnector::from(Arc::clone(&CLIENT_CONFIG));
+            let connector = TlsConnector::from(config);
             let stream = connector.connect(servname, stream).await?;
             Self::Tls(Box::new(TlsStream::Client(stream)))
         } else {
@@ -68,6 +73,13 @@ impl MaybeTlsStream {
        Automatically determines whether or not to use TLS based on the URL scheme.
     */
     pub async fn connect_url(url: Url) -> Result<Self> {
+        Self::connect_url_with_config(url, Arc::clone(&CLIENT_CONFIG)).await
+    }
+
+    /**
+       Connects to the given URL, using the given TLS config if the URL scheme uses TLS.
+    */
+    pub async fn connect_url_with_config(url: Url, config: Arc<ClientConfig>) -> Result<Self> {
         let Some(host) = url.host() else {
             return Err(Error::other("unknown or missing host"));
         };
@@ -82,7 +94,17 @@ impl MaybeTlsStream {
         };
 
         let host = host.to_string();
-        Self::connect(&host, port, use_tls).await
+        Self::connect_with_config(&host, port, use_tls.then_some(config)).await
+    }
+
+    /**
+        Returns the protocol that was negotiated using ALPN during the TLS handshake, if any.
+    */
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        match self {
+            MaybeTlsStream::Plain(_) => None,
+            MaybeTlsStream::Tls(stream) => stream.get_ref().1.alpn_protocol(),
+        }
     }
 
     /**
@@ -182,14 +204,14 @@ pub struct WsStream {
 
 impl WsStream {
     /**
-       Connects to the given URL.
+       Connects to the given URL, using the given TLS config.
 
        Automatically determines whether or not to use TLS based on the URL scheme.
     */
-    pub async fn connect_url(url: Url) -> Result<Self> {
+    pub async fn connect_url(url: Url, config: Arc<ClientConfig>) -> Result<Self> {
         let url_str = url.to_string();
 
-        let stream = MaybeTlsStream::connect_url(url).await?;
+        let stream = MaybeTlsStream::connect_url_with_config(url, config).await?;
         let (inner, _) = async_tungstenite::client_async(url_str, stream)
             .await
             .map_err(Error::other)?;
@@ -1,25 +1,28 @@
 use mlua::prelude::*;
 
-#[derive(Debug, Default, Clone, Copy)]
+use crate::client::tls::ClientTlsConfig;
+
+#[derive(Debug, Default, Clone)]
 pub struct TcpConfig {
-    pub tls: Option<bool>,
+    pub tls: Option<ClientTlsConfig>,
     pub ttl: Option<u32>,
 }
 
 impl FromLua for TcpConfig {
-    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
         if let LuaValue::Nil = value {
             Ok(TcpConfig::default())
         } else if let LuaValue::Boolean(tls) = value {
             Ok(TcpConfig {
-                tls: Some(tls),
+                tls: tls.then(ClientTlsConfig::default),
                 ttl: None,
             })
         } else if let LuaValue::Table(tab) = value {
             let mut this = TcpConfig::default();
 
-            if let Some(tls) = tab.get::<Option<_>>("tls")? {
-                this.tls = Some(tls);
+            match tab.get::<LuaValue>("tls")? {
+                LuaValue::Nil | LuaValue::Boolean(false) => {}
+                tls => this.tls = Some(ClientTlsConfig::from_lua(tls, lua)?),
             }
             if let Some(ttl) = tab.get::<Option<_>>("ttl")? {
                 this.ttl = Some(ttl);
@@ -0,0 +1,282 @@
+use std::{
+    collections::VecDeque,
+    hash::{Hash, Hasher},
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
+
+use bstr::BString;
+use rustls::{
+    ClientConfig, DigitallySignedStruct, Error as TlsError, RootCertStore, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{WebPkiSupportedAlgorithms, ring, verify_tls12_signature, verify_tls13_signature},
+};
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject};
+
+use mlua::prelude::*;
+
+use crate::client::rustls::{
+    ALPN_H2, ALPN_HTTP1, CLIENT_CONFIG, HTTP_CLIENT_CONFIG, initialize_provider,
+};
+
+// NOTE: Loading certificates from the system is slow, so we only ever do it
+// once, and any certificates that fail to load are skipped, same as for curl
+static SYSTEM_ROOTS: LazyLock<Vec<CertificateDer<'static>>> =
+    LazyLock::new(|| rustls_native_certs::load_native_certs().certs);
+
+// NOTE: Building configs is also slow, and the same options are usually given
+// for every request, so we keep the most recently used configs around for reuse,
+// but only a few of them, since their options may contain private keys
+const MAX_CACHED_CONFIGS: usize = 8;
+static CONFIGS: LazyLock<Mutex<VecDeque<ClientTlsConfig>>> = LazyLock::new(Mutex::default);
+
+/**
+    The options that a [`ClientTlsConfig`] was created from.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct ClientTlsOptions {
+    ca: Option<Vec<u8>>,
+    system_certificates: bool,
+    cert: Option<Vec<u8>>,
+    key: Option<Vec<u8>>,
+    skip_verification: bool,
+}
+
+/**
+    TLS configuration for outbound connections, which may trust additional
+    certificate authorities, present a client certificate to the server,
+    or - for local development only - skip verifying the server entirely.
+
+    Two configs are equal if they were created from the same options, which
+    lets connections using one of them be reused for requests using the other.
+*/
+#[derive(Debug, Clone)]
+pub struct ClientTlsConfig {
+    options: Arc<ClientTlsOptions>,
+    config: Arc<ClientConfig>,
+    http_config: Arc<ClientConfig>,
+}
+
+impl ClientTlsConfig {
+    fn new(options: ClientTlsOptions) -> LuaResult<Self> {
+        let mut configs = CONFIGS.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(index) = configs.iter().position(|c| *c.options == options) {
+            let existing = configs.remove(index).expect("index is in bounds");
+            configs.push_front(existing.clone());
+            return Ok(existing);
+        }
+        let this = Self::build(options)?;
+        configs.truncate(MAX_CACHED_CONFIGS - 1);
+        configs.push_front(this.clone());
+        Ok(this)
+    }
+
+    fn build(options: ClientTlsOptions) -> LuaResult<Self> {
+        initialize_provider();
+
+        let builder = ClientConfig::builder();
+        let builder = if options.skip_verification {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(SkipVerification::new()))
+        } else {
+            let mut roots = RootCertStore::empty();
+            if options.system_certificates {
+                roots.add_parsable_certificates(SYSTEM_ROOTS.iter().cloned());
+            } else {
+                roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            }
+            if let Some(ca) = &options.ca {
+                for cert in read_certs(ca, "ca")? {
+                    roots.add(cert).map_err(|e| {
+                        LuaError::RuntimeError(format!(
+                            "Invalid TLS config - failed to add certificate from 'ca'\n{e}"
+                        ))
+                    })?;
+                }
+            }
+            builder.with_root_certificates(roots)
+        };
+
+        let config = match (&options.cert, &options.key) {
+            (Some(cert), Some(key)) => {
+                let certs = read_certs(cert, "cert")?;
+                let key = PrivateKeyDer::from_pem_slice(key).map_err(|e| {
+                    LuaError::RuntimeError(format!(
+                        "Invalid TLS config - failed to read private key\n{e}"
+                    ))
+                })?;
+                builder
+                    .with_client_auth_cert(certs, key)
+                    .map_err(|e| LuaError::RuntimeError(format!("Invalid TLS config - {e}")))?
+            }
+            (None, None) => builder.with_no_client_auth(),
+            (Some(_), None) => {
+                return Err(LuaError::runtime(
+                    "Invalid TLS config - 'cert' was given without 'key'",
+                ));
+            }
+            (None, Some(_)) => {
+                return Err(LuaError::runtime(
+                    "Invalid TLS config - 'key' was given without 'cert'",
+                ));
+            }
+        };
+
+        let mut http_config = config.clone();
+        http_config.alpn_protocols = vec![ALPN_H2.to_vec(), ALPN_HTTP1.to_vec()];
+
+        Ok(Self {
+            options: Arc::new(options),
+            config: Arc::new(config),
+            http_config: Arc::new(http_config),
+        })
+    }
+
+    /**
+        Returns the `rustls` config to use for connections.
+    */
+    pub fn config(&self) -> Arc<ClientConfig> {
+        Arc::clone(&self.config)
+    }
+
+    /**
+        Returns the `rustls` config to use for HTTP connections,
+        which also negotiates HTTP/2 using ALPN.
+
+        See [`HTTP_CLIENT_CONFIG`] for more information.
+    */
+    pub fn http_config(&self) -> Arc<ClientConfig> {
+        Arc::clone(&self.http_config)
+    }
+}
+
+impl Default for ClientTlsConfig {
+    fn default() -> Self {
+        Self {
+            options: Arc::default(),
+            config: Arc::clone(&CLIENT_CONFIG),
+            http_config: Arc::clone(&HTTP_CLIENT_CONFIG),
+        }
+    }
+}
+
+impl PartialEq for ClientTlsConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.options == other.options
+    }
+}
+
+impl Eq for ClientTlsConfig {}
+
+impl Hash for ClientTlsConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.options.hash(state);
+    }
+}
+
+impl FromLua for ClientTlsConfig {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let tab = match value {
+            LuaValue::Nil | LuaValue::Boolean(true) => return Ok(Self::default()),
+            LuaValue::Table(tab) => tab,
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "ClientTlsConfig".to_string(),
+                    message: Some(format!(
+                        "Invalid TLS config - expected table, got {}",
+                        value.type_name()
+                    )),
+                });
+            }
+        };
+
+        let options = ClientTlsOptions {
+            ca: tab.get::<Option<BString>>("ca")?.map(Vec::from),
+            system_certificates: tab
+                .get::<Option<bool>>("systemCertificates")?
+                .unwrap_or_default(),
+            cert: tab.get::<Option<BString>>("cert")?.map(Vec::from),
+            key: tab.get::<Option<BString>>("key")?.map(Vec::from),
+            skip_verification: tab
+                .get::<Option<bool>>("skipVerification")?
+                .unwrap_or_default(),
+        };
+
+        if options == ClientTlsOptions::default() {
+            Ok(Self::default())
+        } else {
+            Self::new(options)
+        }
+    }
+}
+
+pub(crate) fn read_certs(pem: &[u8], field: &str) -> LuaResult<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_slice_iter(pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            LuaError::RuntimeError(format!(
+                "Invalid TLS config - failed to read certificates in '{field}'\n{e}"
+            ))
+        })?;
+    if certs.is_empty() {
+        return Err(LuaError::RuntimeError(format!(
+            "Invalid TLS config - no certificates were found in '{field}'"
+        )));
+    }
+    Ok(certs)
+}
+
+/**
+    Certificate verifier that accepts any certificate from the server.
+
+    Handshake signatures are still verified, so that the server must
+    at least own the certificate that it presents, whatever it may be.
+*/
+#[derive(Debug)]
+struct SkipVerification {
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl SkipVerification {
+    fn new() -> Self {
+        Self {
+            algorithms: ring::default_provider().signature_verification_algorithms,
+        }
+    }
+}
+
+impl ServerCertVerifier for SkipVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, TlsError> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,26 @@
+use mlua::prelude::*;
+
+use crate::client::tls::ClientTlsConfig;
+
+#[derive(Debug, Default, Clone)]
+pub struct WsConfig {
+    pub tls: ClientTlsConfig,
+}
+
+impl FromLua for WsConfig {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        if let LuaValue::Nil = value {
+            Ok(WsConfig::default())
+        } else if let LuaValue::Table(tab) = value {
+            Ok(WsConfig {
+                tls: ClientTlsConfig::from_lua(tab.get("tls")?, lua)?,
+            })
+        } else {
+            Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: String::from("WsConfig"),
+                message: None,
+            })
+        }
+    }
+}
@@ -12,7 +12,9 @@ pub(crate) mod url;
 use crate::shared::{hyper::HyperExecutor, tcp::Tcp};
 
 use self::{
-    client::{stream::WsStream, tcp::TcpConfig},
+    client::{
+        http_client::HttpClient, pool::PoolConfig, stream::WsStream, tcp::TcpConfig, ws::WsConfig,
+    },
     server::config::ServeConfig,
     shared::{request::Request, response::Response, websocket::Websocket},
 };
@@ -42,6 +44,7 @@ pub fn module(lua: Lua) -> LuaResult<LuaTable> {
     let submodule_http = TableBuilder::new(lua.clone())?
         .with_async_function("request", net_http_request)?
         .with_async_function("serve", net_http_serve)?
+        .with_function("client", net_http_client)?
         .build_readonly()?;
 
     let submodule_tcp = TableBuilder::new(lua.clone())?
@@ -65,7 +68,11 @@ pub fn module(lua: Lua) -> LuaResult<LuaTable> {
 }
 
 async fn net_http_request(lua: Lua, req: Request) -> LuaResult<Response> {
-    self::client::send(req, lua).await
+    HttpClient::default_for(&lua).request(lua, req).await
+}
+
+fn net_http_client(_: &Lua, config: PoolConfig) -> LuaResult<HttpClient> {
+    Ok(HttpClient::new(config))
 }
 
 async fn net_http_serve(lua: Lua, (port, config): (u16, ServeConfig)) -> LuaResult<LuaTable> {
@@ -78,9 +85,12 @@ async fn net_tcp_connect(_: Lua, (host, port, config): (String, u16, TcpConfig))
     self::client::connect_tcp(host, port, config).await
 }
 
-async fn net_ws_connect(_: Lua, url: String) -> LuaResult<Websocket<WsStream>> {
+async fn net_ws_connect(
+    _: Lua,
+    (url, config): (String, WsConfig),
+) -> LuaResult<Websocket<WsStream>> {
     let url = url.parse().into_lua_err()?;
-    self::client::connect_ws(url).await
+    self::client::connect_ws(url, config).await
 }
 
 fn net_url_encode(
@@ -2,6 +2,8 @@ use std::net::{IpAddr, Ipv4Addr};
 
 use mlua::prelude::*;
 
+use crate::server::tls::ServeTlsConfig;
+
 const DEFAULT_IP_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
 
 const WEB_SOCKET_UPDGRADE_REQUEST_HANDLER: &str = r#"
@@ -19,6 +21,7 @@ pub struct ServeConfig {
     pub address: IpAddr,
     pub handle_request: LuaFunction,
     pub handle_web_socket: Option<LuaFunction>,
+    pub tls: Option<ServeTlsConfig>,
 }
 
 impl FromLua for ServeConfig {
@@ -29,12 +32,14 @@ impl FromLua for ServeConfig {
                 handle_request: f.clone(),
                 handle_web_socket: None,
                 address: DEFAULT_IP_ADDRESS,
+                tls: None,
             })
         } else if let LuaValue::Table(t) = &value {
             // Table means custom options
             let address: Option<LuaString> = t.get("address")?;
             let handle_request: Option<LuaFunction> = t.get("handleRequest")?;
             let handle_web_socket: Option<LuaFunction> = t.get("handleWebSocket")?;
+            let tls: Option<ServeTlsConfig> = t.get("tls")?;
             if handle_request.is_some() || handle_web_socket.is_some() {
                 let address: IpAddr = match &address {
                     Some(addr) => {
@@ -65,6 +70,7 @@ impl FromLua for ServeConfig {
                             .expect("Failed to create default http responder function")
                     }),
                     handle_web_socket,
+                    tls,
                 })
             } else {
                 Err(LuaError::FromLuaConversionError {
@@ -11,6 +11,31 @@ use async_channel::{Receiver, Sender, unbounded};
 use lune_utils::TableBuilder;
 use mlua::prelude::*;
 
+/**
+    Signal shared between the server and all of its connections,
+    that completes once the server should no longer be running.
+*/
+#[derive(Debug, Clone)]
+pub struct ServeShutdown {
+    stopped: Arc<AtomicBool>,
+    receiver: Receiver<()>,
+}
+
+impl ServeShutdown {
+    /**
+        Waits until the serve handle has either been stopped or dropped.
+
+        Returns `true` if the server was stopped, and `false` if the handle was
+        dropped, meaning that the server should keep running forever instead.
+    */
+    pub async fn wait(&self) -> bool {
+        // NOTE: Nothing is ever sent on this channel, it only gets closed, which
+        // wakes up every receiver at once - sending a message would wake only one
+        self.receiver.recv().await.ok();
+        self.stopped.load(Ordering::SeqCst)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ServeHandle {
     addr: SocketAddr,
@@ -19,14 +44,19 @@ pub struct ServeHandle {
 }
 
 impl ServeHandle {
-    pub fn new(addr: SocketAddr) -> (Self, Receiver<()>) {
+    pub fn new(addr: SocketAddr) -> (Self, ServeShutdown) {
         let (sender, receiver) = unbounded();
+        let shutdown = Arc::new(AtomicBool::new(false));
         let this = Self {
             addr,
-            shutdown: Arc::new(AtomicBool::new(false)),
+            shutdown: Arc::clone(&shutdown),
             sender,
         };
-        (this, receiver)
+        let signal = ServeShutdown {
+            stopped: shutdown,
+            receiver,
+        };
+        (this, signal)
     }
 
     // TODO: Remove this in the next major release to use colon/self
@@ -42,7 +72,6 @@ impl ServeHandle {
                     Err(LuaError::runtime("Server already stopped"))
                 } else {
                     shutdown.store(true, Ordering::SeqCst);
-                    sender.try_send(()).ok();
                     sender.close();
                     Ok(())
                 }
@@ -63,7 +92,6 @@ impl LuaUserData for ServeHandle {
                 Err(LuaError::runtime("Server already stopped"))
             } else {
                 this.shutdown.store(true, Ordering::SeqCst);
-                this.sender.try_send(()).ok();
                 this.sender.close();
                 Ok(())
             }
@@ -2,26 +2,29 @@ use std::{cell::Cell, net::SocketAddr, rc::Rc};
 
 use async_net::TcpListener;
 use futures_lite::pin;
-use hyper::server::conn::http1::Builder as Http1Builder;
+use hyper_util::server::conn::auto::Builder as ConnBuilder;
 
 use mlua::prelude::*;
 use mlua_luau_scheduler::LuaSpawnExt;
 
 use crate::{
+    client::stream::MaybeTlsStream,
     server::{config::ServeConfig, handle::ServeHandle, service::Service},
     shared::{
         futures::{Either, either},
-        hyper::{HyperIo, HyperTimer},
+        hyper::{HyperIo, HyperLocalExecutor, HyperTimer},
     },
 };
 
 pub mod config;
 pub mod handle;
 pub mod service;
+pub mod tls;
 pub mod upgrade;
 
 /**
-    Starts an HTTP server using the given port and configuration.
+    Starts an HTTP server using the given port and configuration,
+    which also terminates TLS for all connections if configured to.
 
     Returns a `ServeHandle` that can be used to gracefully stop the server.
 */
@@ -34,7 +37,7 @@ pub async fn serve(lua: Lua, port: u16, config: ServeConfig) -> LuaResult<ServeH
     };
 
     let listener = TcpListener::bind(address).await?;
-    let (handle, shutdown_rx) = ServeHandle::new(address);
+    let (handle, shutdown) = ServeHandle::new(address);
 
     lua.spawn_local({
         let lua = lua.clone();
@@ -53,10 +56,10 @@ pub async fn serve(lua: Lua, port: u16, config: ServeConfig) -> LuaResult<ServeH
                     }
                 } else {
                     // 1b. Handle is possibly active, we must listen for shutdown
-                    match either(shutdown_rx.recv(), listener.accept()).await {
-                        Either::Left(Ok(())) => break,
-                        Either::Left(Err(_)) => {
-                            // NOTE #1: We will only get a RecvError if the serve handle is dropped,
+                    match either(shutdown.wait(), listener.accept()).await {
+                        Either::Left(true) => break,
+                        Either::Left(false) => {
+                            // NOTE #1: If the server was not stopped, the serve handle was dropped,
                             // this means lua has garbage collected it and the user does not want
                             // to manually stop the server using the serve handle. Run forever.
                             handle_dropped.set(true);
@@ -72,20 +75,35 @@ pub async fn serve(lua: Lua, port: u16, config: ServeConfig) -> LuaResult<ServeH
 
                 // 2. For each connection, spawn a new task to handle it
                 lua.spawn_local({
-                    let rx = shutdown_rx.clone();
-                    let io = HyperIo::from(conn);
+                    let shutdown = shutdown.clone();
 
                     let mut svc = service.clone();
                     svc.address = addr;
 
                     let handle_dropped = Rc::clone(&handle_dropped);
                     async move {
-                        let conn = Http1Builder::new()
+                        // 3. Perform the TLS handshake first, if needed, dropping any
+                        // connections that fail it since there is nobody to respond to
+                        let io = match &svc.config.tls {
+                            Some(tls) => match tls.accept(conn).await {
+                                Some(stream) => HyperIo::from(stream),
+                                None => return,
+                            },
+                            None => HyperIo::from(MaybeTlsStream::from(conn)),
+                        };
+
+                        // 4. Serve the connection using either HTTP/1.1 or HTTP/2, where
+                        // HTTP/2 is negotiated using ALPN for TLS, or detected from the
+                        // connection preface when a client uses it without TLS (h2c)
+                        let mut builder =
+                            ConnBuilder::new(HyperLocalExecutor::new(svc.lua.clone()));
+                        builder
+                            .http1()
                             .writev(false)
                             .timer(HyperTimer)
-                            .keep_alive(true)
-                            .serve_connection(io, svc)
-                            .with_upgrades();
+                            .keep_alive(true);
+                        builder.http2().timer(HyperTimer);
+                        let conn = builder.serve_connection_with_upgrades(io, svc);
                         if handle_dropped.get() {
                             if let Err(_err) = conn.await {
                                 // TODO: Propagate error somehow
@@ -96,9 +114,16 @@ pub async fn serve(lua: Lua, port: u16, config: ServeConfig) -> LuaResult<ServeH
                             // otherwise the already accepted connection will linger and run
                             // even if the stop method has been called on the serve handle
                             pin!(conn);
-                            match either(rx.recv(), conn.as_mut()).await {
-                                Either::Left(Ok(())) => conn.as_mut().graceful_shutdown(),
-                                Either::Left(Err(_)) => {
+                            match either(shutdown.wait(), conn.as_mut()).await {
+                                Either::Left(true) => {
+                                    // Let any in-flight requests finish, but close idle keep-alive
+                                    // connections, which clients may be holding on to for reuse
+                                    conn.as_mut().graceful_shutdown();
+                                    if let Err(_err) = conn.await {
+                                        // TODO: Propagate error somehow
+                                    }
+                                }
+                                Either::Left(false) => {
                                     // Same as note #1
                                     handle_dropped.set(true);
                                     if let Err(_err) = conn.await {
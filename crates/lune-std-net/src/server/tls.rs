@@ -0,0 +1,129 @@
+use std::{sync::Arc, time::Duration};
+
+use async_io::Timer;
+use async_net::TcpStream;
+use bstr::BString;
+use futures_rustls::{TlsAcceptor, TlsStream};
+use rustls::{RootCertStore, ServerConfig, server::WebPkiClientVerifier};
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+
+use mlua::prelude::*;
+
+use crate::{
+    client::{
+        rustls::{ALPN_H2, ALPN_HTTP1, initialize_provider},
+        stream::MaybeTlsStream,
+        tls::read_certs,
+    },
+    shared::futures::{Either, either},
+};
+
+// NOTE: Clients that connect but never finish the handshake would otherwise
+// keep their connection task - and with it, the whole scheduler - alive forever
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/**
+    TLS configuration for `net.serve`, created from a PEM encoded certificate
+    chain and private key, and optionally certificate authorities that clients
+    must present a certificate from.
+*/
+#[derive(Debug, Clone)]
+pub struct ServeTlsConfig {
+    config: Arc<ServerConfig>,
+}
+
+impl ServeTlsConfig {
+    /**
+        Creates a new TLS configuration from PEM encoded
+        certificates and the private key for the first one.
+
+        If certificate authorities are given, clients are required to present
+        a certificate issued by one of them, and are rejected otherwise.
+    */
+    pub fn from_pem(cert: &[u8], key: &[u8], client_ca: Option<&[u8]>) -> LuaResult<Self> {
+        let certs = CertificateDer::pem_slice_iter(cert)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                LuaError::RuntimeError(format!(
+                    "Invalid TLS config - failed to read certificates\n{e}"
+                ))
+            })?;
+        if certs.is_empty() {
+            return Err(LuaError::runtime(
+                "Invalid TLS config - no certificates were found in 'cert'",
+            ));
+        }
+
+        let key = PrivateKeyDer::from_pem_slice(key).map_err(|e| {
+            LuaError::RuntimeError(format!(
+                "Invalid TLS config - failed to read private key\n{e}"
+            ))
+        })?;
+
+        initialize_provider();
+        let builder = ServerConfig::builder();
+        let builder = match client_ca {
+            None => builder.with_no_client_auth(),
+            Some(client_ca) => {
+                let mut roots = RootCertStore::empty();
+                for cert in read_certs(client_ca, "ca")? {
+                    roots.add(cert).map_err(|e| {
+                        LuaError::RuntimeError(format!(
+                            "Invalid TLS config - failed to add certificate from 'ca'\n{e}"
+                        ))
+                    })?;
+                }
+                let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                    .build()
+                    .map_err(|e| LuaError::RuntimeError(format!("Invalid TLS config - {e}")))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+        };
+        let mut config = builder
+            .with_single_cert(certs, key)
+            .map_err(|e| LuaError::RuntimeError(format!("Invalid TLS config - {e}")))?;
+        config.alpn_protocols = vec![ALPN_H2.to_vec(), ALPN_HTTP1.to_vec()];
+
+        Ok(Self {
+            config: Arc::new(config),
+        })
+    }
+
+    /**
+        Performs the TLS handshake for an accepted connection.
+
+        Returns `None` if the handshake failed or timed out.
+    */
+    pub async fn accept(&self, stream: TcpStream) -> Option<MaybeTlsStream> {
+        let acceptor = TlsAcceptor::from(Arc::clone(&self.config));
+        match either(acceptor.accept(stream), Timer::after(HANDSHAKE_TIMEOUT)).await {
+            Either::Left(Ok(stream)) => Some(MaybeTlsStream::from(TlsStream::Server(stream))),
+            Either::Left(Err(_)) | Either::Right(_) => None,
+        }
+    }
+}
+
+impl FromLua for ServeTlsConfig {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let LuaValue::Table(tab) = &value else {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ServeTlsConfig".to_string(),
+                message: Some(String::from(
+                    "Invalid TLS config - expected table with 'cert' and 'key'",
+                )),
+            });
+        };
+
+        let cert: Option<BString> = tab.get("cert")?;
+        let key: Option<BString> = tab.get("key")?;
+        let ca: Option<BString> = tab.get("ca")?;
+        match (cert, key) {
+            (Some(cert), Some(key)) => {
+                Self::from_pem(&cert, &key, ca.as_deref().map(Vec::as_slice))
+            }
+            (None, _) => Err(LuaError::runtime("Invalid TLS config - missing 'cert'")),
+            (_, None) => Err(LuaError::runtime("Invalid TLS config - missing 'key'")),
+        }
+    }
+}
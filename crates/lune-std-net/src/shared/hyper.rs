@@ -3,10 +3,12 @@ use std::{
     io,
     pin::Pin,
     slice,
+    sync::Arc,
     task::{Context, Poll},
     time::{Duration, Instant},
 };
 
+use async_executor::Executor as AsyncExecutor;
 use async_io::Timer;
 use futures_lite::{prelude::*, ready};
 use hyper::rt::{self, Executor, ReadBuf, ReadBufCursor};
@@ -49,6 +51,51 @@ where
     }
 }
 
+// Hyper executor that spawns thread-local futures onto our Lua scheduler,
+// needed for HTTP/2 servers, which run request handlers in their own tasks
+
+#[derive(Debug, Clone)]
+pub struct HyperLocalExecutor {
+    lua: Lua,
+}
+
+impl HyperLocalExecutor {
+    pub fn new(lua: Lua) -> Self {
+        Self { lua }
+    }
+}
+
+impl<Fut: Future + 'static> rt::Executor<Fut> for HyperLocalExecutor {
+    fn execute(&self, fut: Fut) {
+        self.lua.spawn_local(async move {
+            fut.await;
+        });
+    }
+}
+
+// Hyper executor for HTTP/2 client connections, which runs its futures together
+// with the connection itself, so that idle connections never keep Lua running
+
+#[derive(Debug, Clone, Default)]
+pub struct HyperConnExecutor {
+    inner: Arc<AsyncExecutor<'static>>,
+}
+
+impl HyperConnExecutor {
+    pub async fn run<Fut: Future>(&self, fut: Fut) -> Fut::Output {
+        self.inner.run(fut).await
+    }
+}
+
+impl<Fut: Future + Send + 'static> rt::Executor<Fut> for HyperConnExecutor
+where
+    Fut::Output: Send + 'static,
+{
+    fn execute(&self, fut: Fut) {
+        self.inner.spawn(fut).detach();
+    }
+}
+
 // Hyper timer & sleep future wrapper for async-io
 
 #[derive(Debug)]
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, net::SocketAddr};
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
 
 use url::Url;
 
@@ -8,25 +8,42 @@ use mlua::prelude::*;
 
 use crate::{
     body::{ReadableBody, handle_incoming_body},
+    client::tls::ClientTlsConfig,
     shared::{
         headers::{hash_map_to_table, header_map_to_table},
         lua::{lua_table_to_header_map, lua_value_to_method},
     },
 };
 
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
 #[derive(Debug, Clone)]
 pub struct RequestOptions {
     pub decompress: bool,
+    pub http2: Option<bool>,
+    pub timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub max_redirects: usize,
+    pub retries: usize,
+    pub tls: ClientTlsConfig,
 }
 
 impl Default for RequestOptions {
     fn default() -> Self {
-        Self { decompress: true }
+        Self {
+            decompress: true,
+            http2: None,
+            timeout: None,
+            connect_timeout: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            retries: 0,
+            tls: ClientTlsConfig::default(),
+        }
     }
 }
 
 impl FromLua for RequestOptions {
-    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
         if let LuaValue::Nil = value {
             // Nil means default options
             Ok(Self::default())
@@ -38,7 +55,26 @@ impl FromLua for RequestOptions {
                     "Invalid option value for 'decompress' in request options".to_string(),
                 )),
             }?;
-            Ok(Self { decompress })
+            let http2 = match tab.get::<Option<bool>>("http2") {
+                Ok(http2) => Ok(http2),
+                Err(_) => Err(LuaError::RuntimeError(
+                    "Invalid option value for 'http2' in request options".to_string(),
+                )),
+            }?;
+            let timeout = get_seconds_option(&tab, "timeout")?;
+            let connect_timeout = get_seconds_option(&tab, "connectTimeout")?;
+            let max_redirects = get_count_option(&tab, "maxRedirects")?;
+            let retries = get_count_option(&tab, "retries")?;
+            let tls = ClientTlsConfig::from_lua(tab.get("tls")?, lua)?;
+            Ok(Self {
+                decompress,
+                http2,
+                timeout,
+                connect_timeout,
+                max_redirects: max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS),
+                retries: retries.unwrap_or_default(),
+                tls,
+            })
         } else {
             // Anything else is invalid
             Err(LuaError::FromLuaConversionError {
@@ -53,12 +89,43 @@ impl FromLua for RequestOptions {
     }
 }
 
+fn get_seconds_option(tab: &LuaTable, key: &str) -> LuaResult<Option<Duration>> {
+    let Some(secs) = tab.get::<Option<LuaNumber>>(key)? else {
+        return Ok(None);
+    };
+    match Duration::try_from_secs_f64(secs) {
+        Ok(duration) if !duration.is_zero() => Ok(Some(duration)),
+        _ => Err(LuaError::RuntimeError(format!(
+            "Invalid option value for '{key}' in request options - expected a positive number of seconds, got {secs}"
+        ))),
+    }
+}
+
+fn get_count_option(tab: &LuaTable, key: &str) -> LuaResult<Option<usize>> {
+    let Some(count) = tab.get::<Option<LuaNumber>>(key)? else {
+        return Ok(None);
+    };
+    if count < 0.0 || count.fract() != 0.0 || !count.is_finite() {
+        return Err(LuaError::RuntimeError(format!(
+            "Invalid option value for '{key}' in request options - expected a non-negative integer, got {count}"
+        )));
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Ok(Some(count as usize))
+}
+
 #[derive(Debug, Clone)]
 pub struct Request {
     pub(crate) inner: HyperRequest<ReadableBody>,
     pub(crate) address: Option<SocketAddr>,
     pub(crate) redirects: Option<usize>,
     pub(crate) decompress: bool,
+    pub(crate) http2: Option<bool>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) max_redirects: usize,
+    pub(crate) retries: usize,
+    pub(crate) tls: ClientTlsConfig,
 }
 
 impl Request {
@@ -78,6 +145,12 @@ impl Request {
             address: None,
             redirects: None,
             decompress,
+            http2: None,
+            timeout: None,
+            connect_timeout: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            retries: 0,
+            tls: ClientTlsConfig::default(),
         })
     }
 
@@ -164,6 +237,12 @@ impl<B: Into<ReadableBody>> From<HyperRequest<B>> for Request {
             address: None,
             redirects: None,
             decompress: false,
+            http2: None,
+            timeout: None,
+            connect_timeout: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            retries: 0,
+            tls: ClientTlsConfig::default(),
         }
     }
 }
@@ -179,11 +258,18 @@ impl FromLua for Request {
             let mut request = HyperRequest::new(ReadableBody::empty());
             *request.uri_mut() = uri;
 
+            let options = RequestOptions::default();
             Ok(Self {
                 inner: request,
                 address: None,
                 redirects: None,
-                decompress: RequestOptions::default().decompress,
+                decompress: options.decompress,
+                http2: options.http2,
+                timeout: options.timeout,
+                connect_timeout: options.connect_timeout,
+                max_redirects: options.max_redirects,
+                retries: options.retries,
+                tls: options.tls,
             })
         } else if let LuaValue::Table(tab) = value {
             // If we got a table we are able to configure the
@@ -232,6 +318,12 @@ impl FromLua for Request {
                 address: None,
                 redirects: None,
                 decompress: options.decompress,
+                http2: options.http2,
+                timeout: options.timeout,
+                connect_timeout: options.connect_timeout,
+                max_redirects: options.max_redirects,
+                retries: options.retries,
+                tls: options.tls,
             })
         } else {
             // Anything else is invalid
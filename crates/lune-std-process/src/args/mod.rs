@@ -0,0 +1,66 @@
+use std::{ffi::OsString, iter::once};
+
+use mlua::prelude::*;
+
+use lune_utils::process::ProcessArgs;
+
+mod spec;
+
+pub use self::spec::ArgsSpec;
+
+/*
+    NOTE: Exiting must yield the calling thread, which can only be done
+    from Lua, so parsing is wrapped in a chunk that exits when needed
+*/
+const PARSE_ARGS_WRAPPER: &str = r"
+local parsed, code = parse(...)
+if code ~= nil then
+    exit(code)
+end
+return parsed
+";
+
+/**
+    Creates the `process.parseArgs` function, which exits
+    using the given exit function when asked for help or
+    when parsing fails, unless the spec says otherwise.
+*/
+pub fn create_parse_args(lua: &Lua, exit: LuaFunction) -> LuaResult<LuaFunction> {
+    let env = lua.create_table_from(vec![
+        (
+            "parse",
+            LuaValue::Function(lua.create_function(parse_args)?),
+        ),
+        ("exit", LuaValue::Function(exit)),
+    ])?;
+    lua.load(PARSE_ARGS_WRAPPER)
+        .set_name("=__process_parse_args")
+        .set_environment(env)
+        .into_function()
+}
+
+fn parse_args(
+    lua: &Lua,
+    (spec, args): (ArgsSpec, Option<ProcessArgs>),
+) -> LuaResult<(Option<LuaTable>, Option<i32>)> {
+    let command = spec.command()?;
+
+    let args = match args {
+        Some(args) => args,
+        None => lua
+            .app_data_ref::<ProcessArgs>()
+            .ok_or_else(|| LuaError::runtime("Missing process args in Lua app data"))?
+            .clone(),
+    };
+    let argv = once(OsString::from(spec.name())).chain(args);
+
+    match command.try_get_matches_from(argv) {
+        Ok(matches) => Ok((Some(spec.matches_to_table(lua, &matches)?), None)),
+        Err(e) if spec.exit_on_error() => {
+            // NOTE: Help and version are printed to stdout, errors to stderr
+            let _ = e.print();
+            Ok((None, Some(e.exit_code())))
+        }
+        Err(e) => Err(LuaError::RuntimeError(e.render().to_string())),
+    }
+}
@@ -0,0 +1,512 @@
+use std::collections::HashSet;
+
+use clap::{Arg, ArgAction, ArgMatches, Command, builder::PossibleValuesParser, value_parser};
+use mlua::prelude::*;
+
+use lune_utils::TableBuilder;
+
+/**
+    The kind of value that an option or positional argument accepts.
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArgValueKind {
+    #[default]
+    String,
+    Number,
+    Integer,
+}
+
+impl ArgValueKind {
+    fn get<T>(matches: &ArgMatches, id: &str, multiple: bool, lua: &Lua) -> LuaResult<LuaValue>
+    where
+        T: IntoLua + Clone + Send + Sync + 'static,
+    {
+        if multiple {
+            let values = matches
+                .get_many::<T>(id)
+                .map(|values| values.cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+            values.into_lua(lua)
+        } else {
+            matches.get_one::<T>(id).cloned().into_lua(lua)
+        }
+    }
+
+    fn get_value(
+        self,
+        matches: &ArgMatches,
+        id: &str,
+        multiple: bool,
+        lua: &Lua,
+    ) -> LuaResult<LuaValue> {
+        match self {
+            Self::String => Self::get::<String>(matches, id, multiple, lua),
+            Self::Number => Self::get::<f64>(matches, id, multiple, lua),
+            Self::Integer => Self::get::<i64>(matches, id, multiple, lua),
+        }
+    }
+}
+
+impl FromLua for ArgValueKind {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        match &value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::String(s) => match s.to_string_lossy().trim().to_ascii_lowercase().as_str() {
+                "string" => Ok(Self::String),
+                "number" => Ok(Self::Number),
+                "integer" => Ok(Self::Integer),
+                kind => Err(LuaError::RuntimeError(format!(
+                    "Invalid argument type '{kind}', valid types are: string, number, integer"
+                ))),
+            },
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ArgValueKind".to_string(),
+                message: Some(format!(
+                    "Invalid argument type - expected string, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
+
+/**
+    A boolean flag, which is either present or not.
+*/
+#[derive(Debug, Clone)]
+struct FlagSpec {
+    name: String,
+    long: String,
+    short: Option<char>,
+    description: Option<String>,
+}
+
+impl FlagSpec {
+    fn from_table(name: String, t: &LuaTable) -> LuaResult<Self> {
+        Ok(Self {
+            long: t
+                .get::<Option<String>>("long")?
+                .unwrap_or_else(|| name.clone()),
+            short: parse_short(&name, t.get("short")?)?,
+            description: t.get("description")?,
+            name,
+        })
+    }
+
+    fn to_arg(&self) -> Arg {
+        let mut arg = Arg::new(self.name.clone())
+            .long(self.long.clone())
+            .action(ArgAction::SetTrue);
+        if let Some(short) = self.short {
+            arg = arg.short(short);
+        }
+        if let Some(description) = &self.description {
+            arg = arg.help(description.clone());
+        }
+        arg
+    }
+}
+
+/**
+    An option or positional argument, which takes one or more values.
+*/
+#[derive(Debug, Clone)]
+struct ValueSpec {
+    name: String,
+    long: String,
+    short: Option<char>,
+    kind: ArgValueKind,
+    description: Option<String>,
+    defaults: Vec<String>,
+    choices: Option<Vec<String>>,
+    required: bool,
+    multiple: bool,
+}
+
+impl ValueSpec {
+    fn from_table(name: String, t: &LuaTable) -> LuaResult<Self> {
+        let kind: ArgValueKind = t.get("type")?;
+        let multiple = t.get::<Option<bool>>("multiple")?.unwrap_or_default();
+
+        let defaults = match t.get::<LuaValue>("default")? {
+            LuaValue::Nil => Vec::new(),
+            LuaValue::Table(values) if multiple => values
+                .sequence_values::<LuaValue>()
+                .map(|value| default_to_string(&name, value?))
+                .collect::<LuaResult<_>>()?,
+            value => vec![default_to_string(&name, value)?],
+        };
+
+        let choices: Option<Vec<String>> = t.get("choices")?;
+        if choices.is_some() && kind != ArgValueKind::String {
+            return Err(LuaError::RuntimeError(format!(
+                "Invalid argument '{name}' - choices are only supported for string arguments"
+            )));
+        }
+
+        Ok(Self {
+            long: t
+                .get::<Option<String>>("long")?
+                .unwrap_or_else(|| name.clone()),
+            short: parse_short(&name, t.get("short")?)?,
+            kind,
+            description: t.get("description")?,
+            defaults,
+            choices,
+            required: t.get::<Option<bool>>("required")?.unwrap_or_default(),
+            multiple,
+            name,
+        })
+    }
+
+    fn to_arg(&self, positional: bool) -> Arg {
+        let action = if self.multiple {
+            ArgAction::Append
+        } else {
+            ArgAction::Set
+        };
+        let mut arg = Arg::new(self.name.clone())
+            .value_name(self.name.to_uppercase())
+            .required(self.required)
+            .action(action);
+        if positional {
+            if self.multiple {
+                arg = arg.num_args(1..);
+            }
+        } else {
+            arg = arg.long(self.long.clone());
+            if let Some(short) = self.short {
+                arg = arg.short(short);
+            }
+        }
+        arg = match (&self.choices, self.kind) {
+            (Some(choices), _) => arg.value_parser(PossibleValuesParser::new(choices.clone())),
+            (None, ArgValueKind::String) => arg.value_parser(value_parser!(String)),
+            (None, ArgValueKind::Number) => arg.value_parser(value_parser!(f64)),
+            (None, ArgValueKind::Integer) => arg.value_parser(value_parser!(i64)),
+        };
+        if let Some(description) = &self.description {
+            arg = arg.help(description.clone());
+        }
+        if !self.defaults.is_empty() {
+            arg = arg.default_values(self.defaults.clone());
+        }
+        arg
+    }
+
+    fn get_value(&self, matches: &ArgMatches, lua: &Lua) -> LuaResult<LuaValue> {
+        self.kind.get_value(matches, &self.name, self.multiple, lua)
+    }
+
+    /**
+        Returns the first default value that would not be accepted
+        when given as a value for this argument, if any.
+    */
+    fn invalid_default(&self) -> Option<&str> {
+        self.defaults
+            .iter()
+            .find(|value| {
+                if let Some(choices) = &self.choices {
+                    return !choices.contains(value);
+                }
+                match self.kind {
+                    ArgValueKind::String => false,
+                    ArgValueKind::Number => value.parse::<f64>().is_err(),
+                    ArgValueKind::Integer => value.parse::<i64>().is_err(),
+                }
+            })
+            .map(String::as_str)
+    }
+}
+
+/**
+    A declarative specification of the arguments for a command line program.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct ArgsSpec {
+    name: Option<String>,
+    description: Option<String>,
+    version: Option<String>,
+    flags: Vec<FlagSpec>,
+    options: Vec<ValueSpec>,
+    positionals: Vec<ValueSpec>,
+    subcommands: Vec<(String, ArgsSpec)>,
+    subcommand_required: bool,
+    exit_on_error: bool,
+}
+
+impl ArgsSpec {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("script")
+    }
+
+    pub fn exit_on_error(&self) -> bool {
+        self.exit_on_error
+    }
+
+    /**
+        Checks the spec for any mistakes, which would otherwise
+        only be caught by debug assertions when parsing arguments.
+    */
+    fn validate(&self, path: &str) -> LuaResult<()> {
+        let invalid = |message: String| {
+            Err(LuaError::RuntimeError(format!(
+                "Invalid arguments spec for '{path}' - {message}"
+            )))
+        };
+
+        // NOTE: The automatic help and version flags use their names as ids, too
+        let mut reserved = HashSet::from(["help"]);
+        let mut names = HashSet::new();
+        let mut longs = HashSet::from(["help"]);
+        let mut shorts = HashSet::from(['h']);
+        if self.version.is_some() {
+            reserved.insert("version");
+            longs.insert("version");
+            shorts.insert('V');
+        }
+
+        let named = self
+            .flags
+            .iter()
+            .map(|f| (&f.name, &f.long, f.short))
+            .chain(self.options.iter().map(|o| (&o.name, &o.long, o.short)));
+        for (name, long, short) in named {
+            if name.is_empty() || long.is_empty() || long.starts_with('-') {
+                return invalid(format!("'{name}' is not a valid argument name"));
+            }
+            if reserved.contains(name.as_str()) {
+                return invalid(format!("argument name '{name}' is reserved"));
+            }
+            if !names.insert(name.as_str()) {
+                return invalid(format!("argument '{name}' is defined more than once"));
+            }
+            if !longs.insert(long.as_str()) {
+                return invalid(format!("flag '--{long}' is used more than once"));
+            }
+            if let Some(short) = short
+                && !shorts.insert(short)
+            {
+                return invalid(format!("flag '-{short}' is used more than once"));
+            }
+        }
+
+        let last = self.positionals.len().saturating_sub(1);
+        let mut seen_optional = false;
+        for (index, positional) in self.positionals.iter().enumerate() {
+            let name = &positional.name;
+            if name.is_empty() {
+                return invalid(format!("positional argument #{} has no name", index + 1));
+            }
+            if reserved.contains(name.as_str()) {
+                return invalid(format!("argument name '{name}' is reserved"));
+            }
+            if !names.insert(name.as_str()) {
+                return invalid(format!("argument '{name}' is defined more than once"));
+            }
+            if positional.multiple && index != last {
+                return invalid(format!(
+                    "only the last positional argument may take multiple values, but '{name}' is not last"
+                ));
+            }
+            if positional.required && seen_optional {
+                return invalid(format!(
+                    "required positional argument '{name}' can not come after optional ones"
+                ));
+            }
+            seen_optional |= !positional.required;
+        }
+
+        for value in self.options.iter().chain(&self.positionals) {
+            if let Some(default) = value.invalid_default() {
+                return invalid(format!(
+                    "default value '{default}' is not valid for argument '{}'",
+                    value.name
+                ));
+            }
+        }
+
+        for (name, subcommand) in &self.subcommands {
+            if name.is_empty() || name.starts_with('-') {
+                return invalid(format!("'{name}' is not a valid subcommand name"));
+            }
+            if name == "help" {
+                return invalid(format!("subcommand '{name}' is reserved for printing help"));
+            }
+            subcommand.validate(&format!("{path} {name}"))?;
+        }
+
+        Ok(())
+    }
+
+    fn to_command(&self, name: String) -> Command {
+        let mut command = Command::new(name)
+            .args(self.flags.iter().map(FlagSpec::to_arg))
+            .args(self.options.iter().map(|o| o.to_arg(false)))
+            .args(self.positionals.iter().map(|p| p.to_arg(true)))
+            .subcommands(
+                self.subcommands
+                    .iter()
+                    .map(|(name, spec)| spec.to_command(name.clone())),
+            )
+            .subcommand_required(self.subcommand_required);
+        if let Some(description) = &self.description {
+            command = command.about(description.clone());
+        }
+        if let Some(version) = &self.version {
+            command = command.version(version.clone());
+        }
+        command
+    }
+
+    /**
+        Creates a `clap` command for this spec, so that it can be used to parse arguments.
+
+        # Errors
+
+        Errors if the spec is not valid.
+    */
+    pub fn command(&self) -> LuaResult<Command> {
+        self.validate(self.name())?;
+        Ok(self.to_command(self.name().to_string()))
+    }
+
+    /**
+        Converts the matches from parsing arguments using this spec into a Lua table.
+    */
+    pub fn matches_to_table(&self, lua: &Lua, matches: &ArgMatches) -> LuaResult<LuaTable> {
+        let mut flags = TableBuilder::new(lua.clone())?;
+        for flag in &self.flags {
+            flags = flags.with_value(flag.name.as_str(), matches.get_flag(&flag.name))?;
+        }
+
+        let mut options = TableBuilder::new(lua.clone())?;
+        for option in &self.options {
+            options = options.with_value(option.name.as_str(), option.get_value(matches, lua)?)?;
+        }
+
+        let mut positionals = TableBuilder::new(lua.clone())?;
+        for positional in &self.positionals {
+            positionals = positionals.with_value(
+                positional.name.as_str(),
+                positional.get_value(matches, lua)?,
+            )?;
+        }
+
+        let subcommand = match matches.subcommand() {
+            None => LuaValue::Nil,
+            Some((name, sub_matches)) => {
+                let (_, spec) = self
+                    .subcommands
+                    .iter()
+                    .find(|(sub_name, _)| sub_name == name)
+                    .expect("matched subcommand must exist in spec");
+                let table = spec.matches_to_table(lua, sub_matches)?;
+                table.set("name", name)?;
+                LuaValue::Table(table)
+            }
+        };
+
+        TableBuilder::new(lua.clone())?
+            .with_value("flags", flags.build()?)?
+            .with_value("options", options.build()?)?
+            .with_value("positionals", positionals.build()?)?
+            .with_value("subcommand", subcommand)?
+            .build()
+    }
+}
+
+impl FromLua for ArgsSpec {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        let LuaValue::Table(t) = value else {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ArgsSpec".to_string(),
+                message: Some(format!(
+                    "Invalid arguments spec - expected table, got {}",
+                    value.type_name()
+                )),
+            });
+        };
+
+        let mut flags = Vec::new();
+        for (name, flag) in sorted_pairs(t.get("flags")?)? {
+            flags.push(FlagSpec::from_table(name, &flag)?);
+        }
+
+        let mut options = Vec::new();
+        for (name, option) in sorted_pairs(t.get("options")?)? {
+            options.push(ValueSpec::from_table(name, &option)?);
+        }
+
+        let mut positionals = Vec::new();
+        if let Some(list) = t.get::<Option<LuaTable>>("positionals")? {
+            for positional in list.sequence_values::<LuaTable>() {
+                let positional = positional?;
+                let name = positional
+                    .get::<Option<String>>("name")?
+                    .unwrap_or_default();
+                positionals.push(ValueSpec::from_table(name, &positional)?);
+            }
+        }
+
+        let mut subcommands = Vec::new();
+        for (name, subcommand) in sorted_pairs(t.get("subcommands")?)? {
+            let spec: ArgsSpec = lua.unpack(LuaValue::Table(subcommand))?;
+            subcommands.push((name, spec));
+        }
+
+        Ok(Self {
+            name: t.get("name")?,
+            description: t.get("description")?,
+            version: t.get("version")?,
+            flags,
+            options,
+            positionals,
+            subcommands,
+            subcommand_required: t
+                .get::<Option<bool>>("subcommandRequired")?
+                .unwrap_or_default(),
+            exit_on_error: t.get::<Option<bool>>("exitOnError")?.unwrap_or(true),
+        })
+    }
+}
+
+// NOTE: Table iteration order is not stable, so any dictionaries
+// are sorted by key to always generate help in the same order
+fn sorted_pairs(table: Option<LuaTable>) -> LuaResult<Vec<(String, LuaTable)>> {
+    let Some(table) = table else {
+        return Ok(Vec::new());
+    };
+    let mut pairs = table
+        .pairs::<String, LuaTable>()
+        .collect::<LuaResult<Vec<_>>>()?;
+    pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(pairs)
+}
+
+fn parse_short(name: &str, short: Option<String>) -> LuaResult<Option<char>> {
+    let Some(short) = short else {
+        return Ok(None);
+    };
+    let mut chars = short.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c != '-' => Ok(Some(c)),
+        _ => Err(LuaError::RuntimeError(format!(
+            "Invalid argument '{name}' - short flag must be a single character, got '{short}'"
+        ))),
+    }
+}
+
+fn default_to_string(name: &str, value: LuaValue) -> LuaResult<String> {
+    match value {
+        LuaValue::String(s) => Ok(s.to_str()?.to_string()),
+        LuaValue::Integer(i) => Ok(i.to_string()),
+        LuaValue::Number(n) => Ok(n.to_string()),
+        value => Err(LuaError::RuntimeError(format!(
+            "Invalid default value for argument '{name}' - expected string or number, got {}",
+            value.type_name()
+        ))),
+    }
+}
@@ -1,15 +1,34 @@
-use std::process::ExitStatus;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 
 use async_channel::{Receiver, Sender, unbounded};
 use async_process::Child as AsyncChild;
+use blocking::Unblock;
 use futures_util::{FutureExt, select};
+use portable_pty::Child as PtyChild;
 
 use mlua::prelude::*;
 use mlua_luau_scheduler::LuaSpawnExt;
 
 use lune_utils::TableBuilder;
 
-use super::{ChildReader, ChildWriter};
+use crate::signal::{ProcessSignal, kill_child, send_signal};
+
+use super::{
+    ChildReader, ChildWriter,
+    pty::{PtyMaster, PtyReader, PtyWriter, wait_for_pty_child},
+};
+
+/**
+    The underlying process of a child, which is either a regular
+    process with piped stdio, or one attached to a pseudo-terminal.
+*/
+enum ChildHandle {
+    Process(AsyncChild),
+    Pty(Box<dyn PtyChild + Send + Sync>),
+}
 
 #[derive(Debug, Clone)]
 pub struct Child {
@@ -17,20 +36,76 @@ pub struct Child {
     stdout: ChildReader,
     stderr: ChildReader,
     kill_tx: Sender<()>,
-    status_rx: Receiver<Option<ExitStatus>>,
+    status_rx: Receiver<Option<i32>>,
+    pid: u32,
+    process_group: bool,
+    exited: Arc<AtomicBool>,
+    pty: Option<PtyMaster>,
 }
 
 impl Child {
-    pub fn new(lua: &Lua, mut child: AsyncChild) -> Self {
+    pub fn new(lua: &Lua, mut child: AsyncChild, process_group: bool) -> Self {
+        let pid = child.id();
         let stdin = ChildWriter::from(child.stdin.take());
         let stdout = ChildReader::from(child.stdout.take());
         let stderr = ChildReader::from(child.stderr.take());
 
+        Self::spawn_handler(
+            lua,
+            ChildHandle::Process(child),
+            pid,
+            process_group,
+            (stdin, stdout, stderr),
+            None,
+        )
+    }
+
+    pub(super) fn new_pty(
+        lua: &Lua,
+        child: Box<dyn PtyChild + Send + Sync>,
+        pid: u32,
+        master: PtyMaster,
+        reader: Unblock<PtyReader>,
+        writer: Unblock<PtyWriter>,
+    ) -> Self {
+        let stdin = ChildWriter::from(writer);
+        let stdout = ChildReader::from(reader);
+        let stderr = ChildReader::from(None::<async_process::ChildStderr>);
+
+        // Processes in a pty are always session leaders on unix,
+        // meaning they also lead their own process group
+        Self::spawn_handler(
+            lua,
+            ChildHandle::Pty(child),
+            pid,
+            cfg!(unix),
+            (stdin, stdout, stderr),
+            Some(master),
+        )
+    }
+
+    fn spawn_handler(
+        lua: &Lua,
+        handle: ChildHandle,
+        pid: u32,
+        process_group: bool,
+        (stdin, stdout, stderr): (ChildWriter, ChildReader, ChildReader),
+        pty: Option<PtyMaster>,
+    ) -> Self {
         // NOTE: Kill channel is zero size, status is very small
         // and implements Copy, unbounded will be just fine here
         let (kill_tx, kill_rx) = unbounded();
         let (status_tx, status_rx) = unbounded();
-        lua.spawn(handle_child(child, kill_rx, status_tx)).detach();
+        let exited = Arc::new(AtomicBool::new(false));
+        lua.spawn(handle_child(
+            handle,
+            pid,
+            process_group,
+            Arc::clone(&exited),
+            kill_rx,
+            status_tx,
+        ))
+        .detach();
 
         Self {
             stdin,
@@ -38,12 +113,32 @@ impl Child {
             stderr,
             kill_tx,
             status_rx,
+            pid,
+            process_group,
+            exited,
+            pty,
         }
     }
+
+    fn signal(&self, signal: ProcessSignal) -> LuaResult<()> {
+        // Killing goes through the same path as the kill method, which
+        // also makes sending the kill signal work on all platforms
+        if signal == ProcessSignal::Kill {
+            let _ = self.kill_tx.try_send(());
+            return Ok(());
+        }
+        // The process id may be reused by some other process after
+        // the child has exited, so we must never send anything to it
+        if self.exited.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        send_signal(self.pid, signal, self.process_group).into_lua_err()
+    }
 }
 
 impl LuaUserData for Child {
     fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("pid", |_, this| Ok(this.pid));
         fields.add_field_method_get("stdin", |_, this| Ok(this.stdin.clone()));
         fields.add_field_method_get("stdout", |_, this| Ok(this.stdout.clone()));
         fields.add_field_method_get("stderr", |_, this| Ok(this.stderr.clone()));
@@ -54,11 +149,21 @@ impl LuaUserData for Child {
             let _ = this.kill_tx.try_send(());
             Ok(())
         });
+        methods.add_method("signal", |_, this, signal: ProcessSignal| {
+            this.signal(signal)
+        });
+        methods.add_method("resize", |_, this, (cols, rows): (u16, u16)| {
+            match &this.pty {
+                Some(pty) => pty.resize(cols, rows),
+                None => Err(LuaError::runtime(
+                    "Only processes spawned with the 'pty' option can be resized",
+                )),
+            }
+        });
         methods.add_async_method("status", |lua, this, (): ()| {
             let rx = this.status_rx.clone();
             async move {
-                let status = rx.recv().await.ok().flatten();
-                let code = status.and_then(|c| c.code()).unwrap_or(9);
+                let code = rx.recv().await.ok().flatten().unwrap_or(9);
                 TableBuilder::new(lua.clone())?
                     .with_value("ok", code == 0)?
                     .with_value("code", code)?
@@ -69,17 +174,37 @@ impl LuaUserData for Child {
 }
 
 async fn handle_child(
-    mut child: AsyncChild,
+    handle: ChildHandle,
+    pid: u32,
+    process_group: bool,
+    exited: Arc<AtomicBool>,
     kill_rx: Receiver<()>,
-    status_tx: Sender<Option<ExitStatus>>,
+    status_tx: Sender<Option<i32>>,
 ) {
-    let status = select! {
-        s = child.status().fuse() => s.ok(), // FUTURE: Propagate this error somehow?
-        _ = kill_rx.recv().fuse() => {
-            let _ = child.kill(); // Will only error if already killed
-            None
+    let status = match handle {
+        ChildHandle::Process(mut child) => select! {
+            // FUTURE: Propagate this error somehow?
+            s = child.status().fuse() => s.ok().and_then(|s| s.code()),
+            _ = kill_rx.recv().fuse() => {
+                kill_child(&mut child, process_group);
+                None
+            }
+        },
+        ChildHandle::Pty(child) => {
+            let mut killer = child.clone_killer();
+            select! {
+                code = wait_for_pty_child(child).fuse() => code,
+                _ = kill_rx.recv().fuse() => {
+                    if process_group {
+                        let _ = send_signal(pid, ProcessSignal::Kill, true);
+                    }
+                    let _ = killer.kill(); // Will only error if already killed
+                    None
+                }
+            }
         }
     };
+    exited.store(true, Ordering::SeqCst);
 
     // Will only error if there are no receivers waiting for the status
     let _ = status_tx.send(status).await;
@@ -1,80 +1,117 @@
-use std::sync::Arc;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
 
 use async_lock::Mutex as AsyncMutex;
 use async_process::{ChildStderr as AsyncChildStderr, ChildStdout as AsyncChildStdout};
-use futures_lite::{io, prelude::*};
+use blocking::Unblock;
+use bstr::BString;
+use futures_lite::{
+    io::{self, BufReader},
+    prelude::*,
+};
 
 use mlua::prelude::*;
 
+use crate::lines::{self, call_for_line};
+
+use super::pty::PtyReader;
+
 const DEFAULT_BUFFER_SIZE: usize = 1024;
 
 // Inner (plumbing) implementation
 
 #[derive(Debug)]
-enum ChildReaderInner {
+enum ChildReaderStream {
     None,
     Stdout(AsyncChildStdout),
     Stderr(AsyncChildStderr),
+    Pty(Unblock<PtyReader>),
 }
 
-impl ChildReaderInner {
-    async fn read(&mut self, size: usize) -> Result<Vec<u8>, std::io::Error> {
-        if matches!(self, ChildReaderInner::None) {
-            return Ok(Vec::new());
+impl AsyncRead for ChildReaderStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            ChildReaderStream::None => Poll::Ready(Ok(0)),
+            ChildReaderStream::Stdout(stdout) => Pin::new(stdout).poll_read(cx, buf),
+            ChildReaderStream::Stderr(stderr) => Pin::new(stderr).poll_read(cx, buf),
+            ChildReaderStream::Pty(pty) => Pin::new(pty).poll_read(cx, buf),
         }
+    }
+}
 
-        let mut buf = vec![0; size];
-
-        let read = match self {
-            ChildReaderInner::None => unreachable!(),
-            ChildReaderInner::Stdout(stdout) => stdout.read(&mut buf).await?,
-            ChildReaderInner::Stderr(stderr) => stderr.read(&mut buf).await?,
-        };
+#[derive(Debug)]
+struct ChildReaderInner {
+    // NOTE: Reading lines or until some delimiter may read past it,
+    // so all reads must go through this buffer to not lose any data
+    reader: BufReader<ChildReaderStream>,
+}
 
+impl ChildReaderInner {
+    async fn read(&mut self, size: usize) -> Result<Vec<u8>, std::io::Error> {
+        let mut buf = vec![0; size];
+        let read = self.reader.read(&mut buf).await?;
         buf.truncate(read);
-
         Ok(buf)
     }
 
     async fn read_to_end(&mut self) -> Result<Vec<u8>, std::io::Error> {
         let mut buf = Vec::new();
+        // `copy` is much faster compared to `read_to_end` when reading a large amount of data.
+        io::copy(&mut self.reader, &mut buf).await?;
+        Ok(buf)
+    }
 
-        match self {
-            ChildReaderInner::None => {}
-            // `copy` is much faster compared to `read_to_end` when reading a large amount of data.
-            ChildReaderInner::Stdout(stdout) => {
-                io::copy(stdout, &mut buf).await?;
-            }
-            ChildReaderInner::Stderr(stderr) => {
-                io::copy(stderr, &mut buf).await?;
-            }
-        }
+    async fn read_until(&mut self, delimiter: &[u8]) -> Result<Option<Vec<u8>>, std::io::Error> {
+        lines::read_until(&mut self.reader, delimiter).await
+    }
 
-        Ok(buf)
+    async fn read_line(&mut self) -> Result<Option<Vec<u8>>, std::io::Error> {
+        lines::read_line(&mut self.reader).await
+    }
+}
+
+impl From<ChildReaderStream> for ChildReaderInner {
+    fn from(stream: ChildReaderStream) -> Self {
+        Self {
+            reader: BufReader::new(stream),
+        }
     }
 }
 
 impl From<AsyncChildStdout> for ChildReaderInner {
     fn from(stdout: AsyncChildStdout) -> Self {
-        Self::Stdout(stdout)
+        ChildReaderStream::Stdout(stdout).into()
     }
 }
 
 impl From<AsyncChildStderr> for ChildReaderInner {
     fn from(stderr: AsyncChildStderr) -> Self {
-        Self::Stderr(stderr)
+        ChildReaderStream::Stderr(stderr).into()
+    }
+}
+
+impl From<Unblock<PtyReader>> for ChildReaderInner {
+    fn from(pty: Unblock<PtyReader>) -> Self {
+        ChildReaderStream::Pty(pty).into()
     }
 }
 
 impl From<Option<AsyncChildStdout>> for ChildReaderInner {
     fn from(stdout: Option<AsyncChildStdout>) -> Self {
-        stdout.map_or(Self::None, Into::into)
+        stdout.map_or(ChildReaderStream::None.into(), Into::into)
     }
 }
 
 impl From<Option<AsyncChildStderr>> for ChildReaderInner {
     fn from(stderr: Option<AsyncChildStderr>) -> Self {
-        stderr.map_or(Self::None, Into::into)
+        stderr.map_or(ChildReaderStream::None.into(), Into::into)
     }
 }
 
@@ -85,6 +122,13 @@ pub struct ChildReader {
     inner: Arc<AsyncMutex<ChildReaderInner>>,
 }
 
+impl ChildReader {
+    async fn read_line(&self) -> LuaResult<Option<Vec<u8>>> {
+        let mut inner = self.inner.lock().await;
+        inner.read_line().await.into_lua_err()
+    }
+}
+
 impl LuaUserData for ChildReader {
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
         methods.add_async_method("read", |lua, this, size: Option<usize>| {
@@ -108,6 +152,32 @@ impl LuaUserData for ChildReader {
                 Ok(lua.create_string(bytes))
             }
         });
+        methods.add_async_method("readLine", |_, this, (): ()| {
+            let this = this.clone();
+            async move { Ok(this.read_line().await?.map(BString::from)) }
+        });
+        methods.add_async_method("readUntil", |_, this, delimiter: BString| {
+            let inner = this.inner.clone();
+            async move {
+                if delimiter.is_empty() {
+                    return Err(LuaError::runtime("Delimiter must not be an empty string"));
+                }
+                let mut inner = inner.lock().await;
+                let bytes = inner.read_until(&delimiter).await.into_lua_err()?;
+                Ok(bytes.map(BString::from))
+            }
+        });
+        methods.add_async_method("lines", |lua, this, callback: LuaFunction| {
+            let this = this.clone();
+            async move {
+                // NOTE: The reader must not stay locked while the callback runs,
+                // since the callback may want to read from this reader too
+                while let Some(line) = this.read_line().await? {
+                    call_for_line(&lua, &callback, line).await?;
+                }
+                Ok(())
+            }
+        });
     }
 }
 
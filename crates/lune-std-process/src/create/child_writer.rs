@@ -2,17 +2,21 @@ use std::sync::Arc;
 
 use async_lock::Mutex as AsyncMutex;
 use async_process::ChildStdin as AsyncChildStdin;
+use blocking::Unblock;
 use futures_lite::prelude::*;
 
 use bstr::BString;
 use mlua::prelude::*;
 
+use super::pty::PtyWriter;
+
 // Inner (plumbing) implementation
 
 #[derive(Debug)]
 enum ChildWriterInner {
     None,
     Stdin(AsyncChildStdin),
+    Pty(Unblock<PtyWriter>),
 }
 
 impl ChildWriterInner {
@@ -20,14 +24,25 @@ impl ChildWriterInner {
         match self {
             ChildWriterInner::None => Ok(()),
             ChildWriterInner::Stdin(stdin) => stdin.write_all(&data).await,
+            ChildWriterInner::Pty(pty) => {
+                // NOTE: Blocking writers buffer internally, so we must
+                // flush here, or the input would never reach the terminal
+                pty.write_all(&data).await?;
+                pty.flush().await
+            }
         }
     }
 
     async fn close(&mut self) -> Result<(), std::io::Error> {
-        match self {
+        let result = match self {
             ChildWriterInner::None => Ok(()),
             ChildWriterInner::Stdin(stdin) => stdin.close().await,
-        }
+            ChildWriterInner::Pty(pty) => pty.close().await,
+        };
+        // NOTE: Closing only flushes the stream, it must also be dropped
+        // for the child process to actually see the end of its input
+        *self = ChildWriterInner::None;
+        result
     }
 }
 
@@ -37,6 +52,12 @@ impl From<AsyncChildStdin> for ChildWriterInner {
     }
 }
 
+impl From<Unblock<PtyWriter>> for ChildWriterInner {
+    fn from(pty: Unblock<PtyWriter>) -> Self {
+        ChildWriterInner::Pty(pty)
+    }
+}
+
 impl From<Option<AsyncChildStdin>> for ChildWriterInner {
     fn from(stdin: Option<AsyncChildStdin>) -> Self {
         stdin.map_or(Self::None, Into::into)
@@ -1,7 +1,9 @@
 mod child;
 mod child_reader;
 mod child_writer;
+mod pty;
 
 pub use self::child::Child;
 pub use self::child_reader::ChildReader;
 pub use self::child_writer::ChildWriter;
+pub use self::pty::spawn_pty;
@@ -0,0 +1,167 @@
+use std::{
+    fmt,
+    io::{Read, Result as IoResult, Write},
+    sync::{Arc, Mutex},
+};
+
+use blocking::Unblock;
+use portable_pty::{Child as PtyChild, CommandBuilder, MasterPty, PtySize, native_pty_system};
+
+use mlua::prelude::*;
+
+use crate::which::ProgramLookup;
+
+use super::Child;
+
+/**
+    The reading end of a pseudo-terminal, which receives all output from the child process.
+*/
+pub struct PtyReader(Box<dyn Read + Send>);
+
+impl Read for PtyReader {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl fmt::Debug for PtyReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PtyReader").finish_non_exhaustive()
+    }
+}
+
+/**
+    The writing end of a pseudo-terminal, which sends input to the child process.
+
+    Dropping this will send an end-of-file to the child process.
+*/
+pub struct PtyWriter(Box<dyn Write + Send>);
+
+impl Write for PtyWriter {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.0.flush()
+    }
+}
+
+impl fmt::Debug for PtyWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PtyWriter").finish_non_exhaustive()
+    }
+}
+
+/**
+    The controlling side of a pseudo-terminal, which must be kept
+    alive for as long as the child process may use the terminal.
+*/
+#[derive(Clone)]
+pub struct PtyMaster {
+    inner: Arc<Mutex<Box<dyn MasterPty + Send>>>,
+}
+
+impl PtyMaster {
+    pub fn resize(&self, cols: u16, rows: u16) -> LuaResult<()> {
+        if cols == 0 || rows == 0 {
+            return Err(LuaError::runtime(
+                "Invalid terminal size - cols and rows must be greater than zero",
+            ));
+        }
+        let size = PtySize {
+            cols,
+            rows,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        let master = self.inner.lock().expect("poisoned");
+        master
+            .resize(size)
+            .map_err(|e| LuaError::RuntimeError(format!("Failed to resize terminal - {e}")))
+    }
+}
+
+impl fmt::Debug for PtyMaster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PtyMaster").finish_non_exhaustive()
+    }
+}
+
+/**
+    Spawns the given command attached to a new pseudo-terminal of the given size.
+
+    The returned child has the terminal output as its stdout, the terminal
+    input as its stdin, and no stderr, since it is merged into the output.
+
+    The given lookup is only used to create a better error if spawning fails.
+*/
+pub fn spawn_pty(
+    lua: &Lua,
+    cmd: CommandBuilder,
+    size: PtySize,
+    lookup: &ProgramLookup,
+) -> LuaResult<Child> {
+    let pair = native_pty_system()
+        .openpty(size)
+        .map_err(|e| LuaError::RuntimeError(format!("Failed to open terminal - {e}")))?;
+
+    let mut child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| match lookup.find() {
+            Err(not_found) => LuaError::external(not_found),
+            Ok(_) => LuaError::RuntimeError(format!("Failed to spawn process - {e}")),
+        })?;
+
+    // NOTE: The slave side must be closed here, or reading the
+    // output would never reach the end, even after the child exits
+    drop(pair.slave);
+
+    let streams = pair
+        .master
+        .try_clone_reader()
+        .and_then(|reader| Ok((reader, pair.master.take_writer()?)));
+    let (reader, writer) = match streams {
+        Ok(streams) => streams,
+        Err(e) => {
+            let _ = child.kill();
+            return Err(LuaError::RuntimeError(format!(
+                "Failed to open terminal - {e}"
+            )));
+        }
+    };
+
+    let Some(pid) = child.process_id() else {
+        let _ = child.kill();
+        return Err(LuaError::runtime(
+            "Failed to spawn process - missing process id",
+        ));
+    };
+
+    let master = PtyMaster {
+        inner: Arc::new(Mutex::new(pair.master)),
+    };
+
+    Ok(Child::new_pty(
+        lua,
+        child,
+        pid,
+        master,
+        Unblock::new(PtyReader(reader)),
+        Unblock::new(PtyWriter(writer)),
+    ))
+}
+
+/**
+    Waits for the given pseudo-terminal child process to exit,
+    returning its exit code, or `None` if it exited from a signal.
+*/
+pub async fn wait_for_pty_child(mut child: Box<dyn PtyChild + Send + Sync>) -> Option<i32> {
+    let status = blocking::unblock(move || child.wait()).await.ok()?;
+    if status.signal().is_some() {
+        None
+    } else {
+        i32::try_from(status.exit_code()).ok()
+    }
+}
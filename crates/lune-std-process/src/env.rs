@@ -0,0 +1,77 @@
+use std::path::MAIN_SEPARATOR;
+
+use mlua::prelude::*;
+
+use lune_utils::{
+    TableBuilder,
+    path::{get_current_dir, set_current_dir},
+    process::ProcessEnv,
+};
+
+const ENV_TABLE_KEY: &str = "__PROCESS_ENV_TABLE";
+
+/**
+    Gets the current working directory as a string, ending with a path separator.
+*/
+pub fn current_dir_string() -> String {
+    let mut cwd_str = get_current_dir()
+        .to_str()
+        .expect("cwd should be valid UTF-8")
+        .to_string();
+    if !cwd_str.ends_with(MAIN_SEPARATOR) {
+        cwd_str.push(MAIN_SEPARATOR);
+    }
+    cwd_str
+}
+
+/**
+    Creates the metatable for the process library, which makes
+    sure that `cwd` always reflects the current working directory.
+*/
+pub fn create_process_metatable(lua: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::new(lua.clone())?
+        .with_function("__index", |_, (_, key): (LuaTable, LuaString)| {
+            Ok((key == "cwd").then(current_dir_string))
+        })?
+        .build_readonly()
+}
+
+/**
+    Creates the `process.env` table, containing all variables in the given env.
+
+    This is a plain table, so that it can be iterated and used like any other
+    table, and it is read back using `current_env` whenever spawning processes.
+*/
+pub fn create_env_table(lua: &Lua, env: &ProcessEnv) -> LuaResult<LuaTable> {
+    let table = env.into_plain_lua_table(lua.clone())?;
+    lua.set_named_registry_value(ENV_TABLE_KEY, &table)?;
+    Ok(table)
+}
+
+/**
+    Gets the current environment variables, including any changes made to `process.env`.
+
+    Any keys and values in `process.env` that are not strings, or that are
+    not valid environment variables, are ignored when reading it back.
+*/
+pub fn current_env(lua: &Lua) -> LuaResult<Option<ProcessEnv>> {
+    let Some(table) = lua.named_registry_value::<Option<LuaTable>>(ENV_TABLE_KEY)? else {
+        return Ok(lua.app_data_ref::<ProcessEnv>().map(|env| env.clone()));
+    };
+    let env = ProcessEnv::empty();
+    for pair in table.pairs::<LuaValue, LuaValue>() {
+        if let (LuaValue::String(key), LuaValue::String(value)) = pair? {
+            env.set_value_bytes(key.as_bytes(), value.as_bytes().to_vec());
+        }
+    }
+    Ok(Some(env))
+}
+
+pub fn process_chdir(_: &Lua, path: String) -> LuaResult<String> {
+    set_current_dir(&path).map_err(|e| {
+        LuaError::RuntimeError(format!(
+            "Failed to change working directory to '{path}'\n{e}"
+        ))
+    })?;
+    Ok(current_dir_string())
+}
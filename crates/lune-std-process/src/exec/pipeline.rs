@@ -0,0 +1,243 @@
+use std::{io::ErrorKind, process::Stdio};
+
+use async_process::Child;
+use futures_lite::prelude::*;
+use futures_util::{future::try_join_all, try_join};
+
+use mlua::prelude::*;
+
+use lune_utils::{TableBuilder, process::ProcessArgs};
+
+use super::{WaitForChildOptions, exit_code, wait_for_child::wait_for_child};
+use crate::{
+    options::{ProcessSpawnOptions, ProcessSpawnOptionsStdioKind},
+    signal::kill_child,
+};
+
+/**
+    A single command in a pipeline, given from lua as `{ program, args?, options? }`.
+*/
+#[derive(Debug, Clone)]
+pub struct PipelineCommand {
+    program: String,
+    args: ProcessArgs,
+    options: ProcessSpawnOptions,
+}
+
+impl FromLua for PipelineCommand {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let LuaValue::Table(t) = value else {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "PipelineCommand".to_string(),
+                message: Some(format!(
+                    "Invalid pipeline command - expected table, got {}",
+                    value.type_name()
+                )),
+            });
+        };
+        Ok(Self {
+            program: t
+                .get(1)
+                .context("Pipeline commands must start with a program name")?,
+            args: t.get(2)?,
+            options: t.get(3)?,
+        })
+    }
+}
+
+struct PipelineChild {
+    child: Child,
+    options: WaitForChildOptions,
+}
+
+/**
+    Spawns all of the given commands, with the stdout of each
+    command connected to the stdin of the next command.
+
+    If any of the commands fail to spawn, all previously spawned commands are killed.
+*/
+async fn spawn_all(
+    commands: Vec<PipelineCommand>,
+    has_stdin: bool,
+) -> LuaResult<Vec<PipelineChild>> {
+    let last = commands.len() - 1;
+    let mut children: Vec<PipelineChild> = Vec::with_capacity(commands.len());
+
+    for (index, command) in commands.into_iter().enumerate() {
+        let piped_stdin = has_stdin && index == 0;
+        let result = spawn_one(command, index, last, piped_stdin, children.last_mut()).await;
+        match result {
+            Ok(child) => children.push(child),
+            Err(e) => {
+                for spawned in &mut children {
+                    kill_child(&mut spawned.child, spawned.options.process_group);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(children)
+}
+
+async fn spawn_one(
+    command: PipelineCommand,
+    index: usize,
+    last: usize,
+    piped_stdin: bool,
+    previous: Option<&mut PipelineChild>,
+) -> LuaResult<PipelineChild> {
+    let stdio = &command.options.stdio;
+    if index > 0 && stdio.stdin.is_some() {
+        return Err(LuaError::RuntimeError(format!(
+            "Invalid options for pipeline command #{} - only the first command may be given stdin",
+            index + 1
+        )));
+    }
+    if let Some(option) = command.options.create_only_option() {
+        return Err(LuaError::RuntimeError(format!(
+            "Invalid options for pipeline command #{} - option '{option}' is only supported by process.create",
+            index + 1
+        )));
+    }
+    if index < last
+        && (stdio.stdout != ProcessSpawnOptionsStdioKind::Default
+            || stdio.stdout_callback.is_some())
+    {
+        return Err(LuaError::RuntimeError(format!(
+            "Invalid options for pipeline command #{} - only the last command may set stdout",
+            index + 1
+        )));
+    }
+
+    let stdin_stdio = match previous.and_then(|p| p.child.stdout.take()) {
+        Some(stdout) => stdout.into_stdio().await?,
+        None if piped_stdin => Stdio::piped(),
+        None => Stdio::null(),
+    };
+
+    // NOTE: Stdout of all commands except the last one is read by the next
+    // command, so there is nothing left to read when waiting for the child
+    let (stdout_stdio, stdout) = if index == last {
+        (stdio.stdout.as_stdio(), stdio.stdout)
+    } else {
+        (Stdio::piped(), ProcessSpawnOptionsStdioKind::None)
+    };
+    let options = WaitForChildOptions {
+        stdout,
+        stderr: stdio.stderr,
+        stdout_callback: stdio.stdout_callback.clone(),
+        stderr_callback: stdio.stderr_callback.clone(),
+        timeout: command.options.timeout,
+        process_group: command.options.process_group,
+    };
+
+    let lookup = command.options.program_lookup(&command.program);
+    let child = command
+        .options
+        .into_command(command.program, command.args)
+        .stdin(stdin_stdio)
+        .stdout(stdout_stdio)
+        .stderr(options.stderr.as_stdio())
+        .spawn()
+        .map_err(|e| lookup.spawn_error(e))?;
+
+    Ok(PipelineChild { child, options })
+}
+
+pub async fn pipeline(lua: Lua, mut commands: Vec<PipelineCommand>) -> LuaResult<LuaTable> {
+    let Some(first) = commands.first_mut() else {
+        return Err(LuaError::runtime(
+            "Invalid pipeline - must contain at least one command",
+        ));
+    };
+    let stdin = first.options.stdio.stdin.take();
+
+    let (mut children, options): (Vec<_>, Vec<_>) = spawn_all(commands, stdin.is_some())
+        .await?
+        .into_iter()
+        .map(|c| (c.child, c.options))
+        .unzip();
+    let child_stdin = children[0].stdin.take();
+    let process_groups = options.iter().map(|o| o.process_group).collect::<Vec<_>>();
+
+    let write_stdin = async move {
+        if let (Some(stdin), Some(mut child_stdin)) = (stdin, child_stdin) {
+            // NOTE: The first command may exit without reading all of its
+            // input, which is not an error for the pipeline as a whole
+            match child_stdin.write_all(&stdin).await {
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => {}
+                res => res.into_lua_err()?,
+            }
+        }
+        Ok::<_, LuaError>(())
+    };
+
+    let wait_all = try_join_all(
+        children
+            .iter_mut()
+            .zip(options)
+            .map(|(child, options)| wait_for_child(&lua, child, options)),
+    );
+
+    // NOTE: Writing stdin or waiting for any of the commands may fail, in which case
+    // we stop waiting for the rest of them, and must make sure they are not left running
+    let results = match try_join!(wait_all, write_stdin) {
+        Ok((results, ())) => results,
+        Err(e) => {
+            for (child, process_group) in children.iter_mut().zip(process_groups) {
+                kill_child(child, process_group);
+            }
+            for child in &mut children {
+                let _ = child.status().await;
+            }
+            return Err(e);
+        }
+    };
+
+    /*
+        NOTE: Similar to `set -o pipefail` in most shells, the pipeline
+        only succeeds if all of the commands succeeded, and the final
+        code is the code of the last command that did not succeed
+    */
+    let mut code = 0;
+    let mut timed_out = false;
+    let mut stderr = Vec::new();
+    let mut statuses = Vec::with_capacity(results.len());
+    for res in &results {
+        let res_code = exit_code(res);
+        if res_code != 0 {
+            code = res_code;
+        }
+        timed_out |= res.timed_out;
+        stderr.extend_from_slice(&res.stderr);
+        statuses.push(
+            TableBuilder::new(lua.clone())?
+                .with_value("ok", res_code == 0 && !res.timed_out)?
+                .with_value("code", res_code)?
+                .with_value("stderr", lua.create_string(&res.stderr)?)?
+                .with_value("timedOut", res.timed_out)?
+                .build_readonly()?,
+        );
+    }
+
+    let stdout = lua.create_string(
+        results
+            .last()
+            .map(|r| r.stdout.as_slice())
+            .unwrap_or_default(),
+    )?;
+    let stderr = lua.create_string(&stderr)?;
+    let statuses = TableBuilder::new(lua.clone())?
+        .with_sequential_values(statuses)?
+        .build_readonly()?;
+    TableBuilder::new(lua)?
+        .with_value("ok", code == 0 && !timed_out)?
+        .with_value("code", code)?
+        .with_value("stdout", stdout)?
+        .with_value("stderr", stderr)?
+        .with_value("timedOut", timed_out)?
+        .with_value("statuses", statuses)?
+        .build_readonly()
+}
@@ -14,22 +14,15 @@ where
 {
     #[pin]
     writer: &'a mut W,
-    buffer: Vec<u8>,
+    buffer: &'a mut Vec<u8>,
 }
 
 impl<'a, W> AsyncTeeWriter<'a, W>
 where
     W: AsyncWrite + Unpin,
 {
-    pub fn new(writer: &'a mut W) -> Self {
-        Self {
-            writer,
-            buffer: Vec::new(),
-        }
-    }
-
-    pub fn into_vec(self) -> Vec<u8> {
-        self.buffer
+    pub fn new(writer: &'a mut W, buffer: &'a mut Vec<u8>) -> Self {
+        Self { writer, buffer }
     }
 }
 
@@ -45,8 +38,7 @@ where
         let mut this = self.project();
         match this.writer.as_mut().poll_write(cx, buf) {
             Poll::Ready(res) => {
-                Write::write_all(&mut this.buffer, buf)
-                    .expect("Failed to write to internal tee buffer");
+                Write::write_all(this.buffer, buf).expect("Failed to write to internal tee buffer");
                 Poll::Ready(res)
             }
             Poll::Pending => Poll::Pending,
@@ -1,72 +1,181 @@
-use std::{io::stdout, process::ExitStatus};
+use std::{io::stdout, process::ExitStatus, time::Duration};
 
 use mlua::prelude::*;
 
+use async_io::Timer;
 use async_process::Child;
 use blocking::Unblock;
-use futures_lite::{io, prelude::*};
+use futures_lite::{
+    io::{self, BufReader},
+    prelude::*,
+};
 use futures_util::try_join;
 
 use super::tee_writer::AsyncTeeWriter;
-use crate::options::ProcessSpawnOptionsStdioKind;
+use crate::{lines::read_lines_with, options::ProcessSpawnOptionsStdioKind, signal::kill_child};
+
+/**
+    How long to keep reading output after a child process timed out and was killed.
+
+    Any processes that the child started may still be holding on to its output
+    pipes, and we must not keep waiting for those to finish, or be killed.
+*/
+const TIMEOUT_READ_GRACE: Duration = Duration::from_millis(100);
 
 #[derive(Debug, Clone)]
 pub(super) struct WaitForChildResult {
     pub status: ExitStatus,
     pub stdout: Vec<u8>,
     pub stderr: Vec<u8>,
+    pub timed_out: bool,
+}
+
+/**
+    Options for how to wait for a child process to exit.
+
+    If a callback is given for stdout or stderr, it will receive
+    all output for that stream line-by-line, instead of capturing it.
+*/
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WaitForChildOptions {
+    pub stdout: ProcessSpawnOptionsStdioKind,
+    pub stderr: ProcessSpawnOptionsStdioKind,
+    pub stdout_callback: Option<LuaFunction>,
+    pub stderr_callback: Option<LuaFunction>,
+    pub timeout: Option<Duration>,
+    pub process_group: bool,
 }
 
+/**
+    Reads output using the given stdio kind, into the given buffer.
+
+    Output is written to the buffer as soon as it is read, so that
+    it is kept even if reading stops before reaching the end.
+*/
 async fn read_with_stdio_kind<R>(
+    lua: &Lua,
     read_from: Option<R>,
     kind: ProcessSpawnOptionsStdioKind,
-) -> LuaResult<Vec<u8>>
+    callback: Option<&LuaFunction>,
+    buffer: &mut Vec<u8>,
+) -> LuaResult<()>
 where
     R: AsyncRead + Unpin,
 {
-    Ok(match kind {
-        ProcessSpawnOptionsStdioKind::None | ProcessSpawnOptionsStdioKind::Forward => Vec::new(),
+    if let Some(callback) = callback {
+        let read_from = read_from.expect("read_from must be Some when given a callback");
+        read_lines_with(lua, &mut BufReader::new(read_from), callback).await?;
+        return Ok(());
+    }
+    match kind {
+        ProcessSpawnOptionsStdioKind::None | ProcessSpawnOptionsStdioKind::Forward => {}
         ProcessSpawnOptionsStdioKind::Default => {
             let mut read_from =
                 read_from.expect("read_from must be Some when stdio kind is Default");
 
-            let mut buffer = Vec::new();
-
-            io::copy(&mut read_from, &mut buffer).await.into_lua_err()?;
-
-            buffer
+            io::copy(&mut read_from, buffer).await.into_lua_err()?;
         }
         ProcessSpawnOptionsStdioKind::Inherit => {
             let mut read_from =
                 read_from.expect("read_from must be Some when stdio kind is Inherit");
 
             let mut stdout = Unblock::new(stdout());
-            let mut tee = AsyncTeeWriter::new(&mut stdout);
+            let mut tee = AsyncTeeWriter::new(&mut stdout, buffer);
 
             io::copy(&mut read_from, &mut tee).await.into_lua_err()?;
+        }
+    }
+    Ok(())
+}
 
-            tee.into_vec()
+async fn status_with_timeout(
+    child: &mut Child,
+    timeout: Option<Duration>,
+    process_group: bool,
+) -> io::Result<(ExitStatus, bool)> {
+    if let Some(timeout) = timeout {
+        let timer = async {
+            Timer::after(timeout).await;
+            None
+        };
+        if let Some(status) = async { Some(child.status().await) }.or(timer).await {
+            return status.map(|s| (s, false));
         }
-    })
+        kill_child(child, process_group);
+        return child.status().await.map(|s| (s, true));
+    }
+    child.status().await.map(|s| (s, false))
 }
 
 pub(super) async fn wait_for_child(
-    mut child: Child,
-    stdout_kind: ProcessSpawnOptionsStdioKind,
-    stderr_kind: ProcessSpawnOptionsStdioKind,
+    lua: &Lua,
+    child: &mut Child,
+    options: WaitForChildOptions,
 ) -> LuaResult<WaitForChildResult> {
     let stdout_opt = child.stdout.take();
     let stderr_opt = child.stderr.take();
 
-    let (status, stdout, stderr) = try_join!(
-        async { child.status().await.into_lua_err() },
-        read_with_stdio_kind(stdout_opt, stdout_kind),
-        read_with_stdio_kind(stderr_opt, stderr_kind)
-    )?;
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+
+    let read_output = async {
+        try_join!(
+            read_with_stdio_kind(
+                lua,
+                stdout_opt,
+                options.stdout,
+                options.stdout_callback.as_ref(),
+                &mut stdout,
+            ),
+            read_with_stdio_kind(
+                lua,
+                stderr_opt,
+                options.stderr,
+                options.stderr_callback.as_ref(),
+                &mut stderr,
+            )
+        )
+    };
+
+    // NOTE: Processes started by the child may inherit its output pipes, and
+    // keep them open after the child exits, so when using a timeout, we also
+    // stop reading output shortly after it passes, same as for the child
+    let read_output = async {
+        match options.timeout {
+            None => read_output.await,
+            Some(timeout) => {
+                let deadline = async {
+                    Timer::after(timeout + TIMEOUT_READ_GRACE).await;
+                    Ok(((), ()))
+                };
+                read_output.or(deadline).await
+            }
+        }
+    };
+
+    let joined = try_join!(
+        async {
+            status_with_timeout(child, options.timeout, options.process_group)
+                .await
+                .into_lua_err()
+        },
+        read_output
+    );
+
+    // NOTE: Reading output may fail, or a callback may error, and we
+    // must then make sure that the child process does not keep running
+    let ((status, timed_out), _) = match joined {
+        Ok(joined) => joined,
+        Err(e) => {
+            kill_child(child, options.process_group);
+            return Err(e);
+        }
+    };
 
     Ok(WaitForChildResult {
         status,
         stdout,
         stderr,
+        timed_out,
     })
 }
@@ -2,7 +2,6 @@
 
 use std::{
     env::consts::{ARCH, OS},
-    path::MAIN_SEPARATOR,
     process::Stdio,
 };
 
@@ -11,15 +10,22 @@ use mlua_luau_scheduler::Functions;
 
 use lune_utils::{
     TableBuilder,
-    path::get_current_dir,
     process::{ProcessArgs, ProcessEnv},
 };
 
+mod args;
 mod create;
+mod env;
 mod exec;
+mod lines;
 mod options;
+mod signal;
+mod stats;
+mod which;
 
+use self::exec::WaitForChildOptions;
 use self::options::ProcessSpawnOptions;
+use self::signal::ProcessSignal;
 
 const TYPEDEFS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/types.d.luau"));
 
@@ -40,13 +46,9 @@ pub fn typedefs() -> String {
 */
 #[allow(clippy::missing_panics_doc)]
 pub fn module(lua: Lua) -> LuaResult<LuaTable> {
-    let mut cwd_str = get_current_dir()
-        .to_str()
-        .expect("cwd should be valid UTF-8")
-        .to_string();
-    if !cwd_str.ends_with(MAIN_SEPARATOR) {
-        cwd_str.push(MAIN_SEPARATOR);
-    }
+    // Uptime is measured from when the process library was first created,
+    // which happens when the runtime starts, not when it is first required
+    stats::mark_start();
 
     // Create constants for OS & processor architecture
     let os = lua.create_string(OS.to_lowercase())?;
@@ -66,7 +68,8 @@ pub fn module(lua: Lua) -> LuaResult<LuaTable> {
     let process_env = lua
         .app_data_ref::<ProcessEnv>()
         .ok_or_else(|| LuaError::runtime("Missing process env in Lua app data"))?
-        .into_plain_lua_table(lua.clone())?;
+        .clone();
+    let process_env = env::create_env_table(&lua, &process_env)?;
 
     process_args.set_readonly(true);
 
@@ -74,17 +77,32 @@ pub fn module(lua: Lua) -> LuaResult<LuaTable> {
     let fns = Functions::new(lua.clone())?;
     let process_exit = fns.exit;
 
-    // Create the full process table
+    // Argument parsing may also need to exit, when asked for help or given invalid arguments
+    let process_parse_args = args::create_parse_args(&lua, process_exit.clone())?;
+
+    // Create the full process table, where cwd is kept up to date by its metatable
+    let process_meta = env::create_process_metatable(&lua)?;
     TableBuilder::new(lua)?
         .with_value("os", os)?
         .with_value("arch", arch)?
         .with_value("endianness", endianness)?
+        .with_value("pid", std::process::id())?
         .with_value("args", process_args)?
-        .with_value("cwd", cwd_str)?
         .with_value("env", process_env)?
         .with_value("exit", process_exit)?
+        .with_function("chdir", env::process_chdir)?
+        .with_value("parseArgs", process_parse_args)?
         .with_async_function("exec", process_exec)?
         .with_function("create", process_create)?
+        .with_function("which", which::process_which)?
+        .with_async_function("pipeline", process_pipeline)?
+        .with_function("kill", process_kill)?
+        .with_function("isRunning", process_is_running)?
+        .with_function("onSignal", signal::on_signal)?
+        .with_function("uptime", stats::process_uptime)?
+        .with_function("memoryUsage", stats::process_memory_usage)?
+        .with_function("cpuUsage", stats::process_cpu_usage)?
+        .with_metatable(process_meta)?
         .build_readonly()
 }
 
@@ -92,9 +110,21 @@ async fn process_exec(
     lua: Lua,
     (program, args, mut options): (String, ProcessArgs, ProcessSpawnOptions),
 ) -> LuaResult<LuaTable> {
+    if let Some(option) = options.create_only_option() {
+        return Err(LuaError::RuntimeError(format!(
+            "Invalid options - option '{option}' is only supported by process.create"
+        )));
+    }
+
     let stdin = options.stdio.stdin.take();
-    let stdout = options.stdio.stdout;
-    let stderr = options.stdio.stderr;
+    let wait_options = WaitForChildOptions {
+        stdout: options.stdio.stdout,
+        stderr: options.stdio.stderr,
+        stdout_callback: options.stdio.stdout_callback.take(),
+        stderr_callback: options.stdio.stderr_callback.take(),
+        timeout: options.timeout,
+        process_group: options.process_group,
+    };
 
     let stdin_stdio = if stdin.is_some() {
         Stdio::piped()
@@ -102,26 +132,71 @@ async fn process_exec(
         Stdio::null()
     };
 
+    let lookup = options.program_lookup(&program);
     let child = options
         .into_command(program, args)
         .stdin(stdin_stdio)
-        .stdout(stdout.as_stdio())
-        .stderr(stderr.as_stdio())
-        .spawn()?;
+        .stdout(wait_options.stdout.as_stdio())
+        .stderr(wait_options.stderr.as_stdio())
+        .spawn()
+        .map_err(|e| lookup.spawn_error(e))?;
+
+    exec::exec(lua, child, stdin, wait_options).await
+}
 
-    exec::exec(lua, child, stdin, stdout, stderr).await
+async fn process_pipeline(lua: Lua, commands: Vec<exec::PipelineCommand>) -> LuaResult<LuaTable> {
+    exec::pipeline(lua, commands).await
 }
 
 fn process_create(
     lua: &Lua,
     (program, args, options): (String, ProcessArgs, ProcessSpawnOptions),
 ) -> LuaResult<LuaValue> {
+    if let Some(option) = options.exec_only_option() {
+        return Err(LuaError::RuntimeError(format!(
+            "Invalid options - option '{option}' is only supported by process.exec"
+        )));
+    }
+
+    let lookup = options.program_lookup(&program);
+    if let Some(pty) = options.pty {
+        let cmd = options.into_pty_command(program, args)?;
+        return create::spawn_pty(lua, cmd, pty.size(), &lookup)?.into_lua(lua);
+    }
+
+    // NOTE: Detached processes must not be connected to us through any pipes,
+    // since they would then be closed or broken as soon as we exit, and are
+    // always in their own session, thus also leading their own process group
+    let detached = options.detached;
+    let process_group = options.process_group || (detached && cfg!(unix));
+    let stdio = || {
+        if detached {
+            Stdio::null()
+        } else {
+            Stdio::piped()
+        }
+    };
+
     let child = options
         .into_command(program, args)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
+        .stdin(stdio())
+        .stdout(stdio())
+        .stderr(stdio())
+        .spawn()
+        .map_err(|e| lookup.spawn_error(e))?;
+
+    create::Child::new(lua, child, process_group).into_lua(lua)
+}
+
+fn process_kill(_: &Lua, (pid, signal): (LuaNumber, Option<ProcessSignal>)) -> LuaResult<bool> {
+    let pid = signal::parse_pid(pid)?;
+    match signal::send_signal(pid, signal.unwrap_or(ProcessSignal::Kill), false) {
+        Ok(()) => Ok(true),
+        Err(_) if !signal::is_running(pid) => Ok(false),
+        Err(e) => Err(e.into_lua_err()),
+    }
+}
 
-    create::Child::new(lua, child).into_lua(lua)
+fn process_is_running(_: &Lua, pid: LuaNumber) -> LuaResult<bool> {
+    Ok(signal::is_running(signal::parse_pid(pid)?))
 }
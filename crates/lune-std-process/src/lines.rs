@@ -0,0 +1,86 @@
+use bstr::BString;
+use futures_lite::{io, prelude::*};
+
+use mlua::prelude::*;
+use mlua_luau_scheduler::LuaSchedulerExt;
+
+/**
+    Reads until the given delimiter, returning everything before it.
+
+    If the reader ends before finding the delimiter, all remaining
+    data is returned instead, or `None` if there was no data left.
+
+    # Panics
+
+    Panics if the delimiter is empty.
+*/
+pub async fn read_until<R>(reader: &mut R, delimiter: &[u8]) -> io::Result<Option<Vec<u8>>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let last = *delimiter
+        .last()
+        .expect("delimiter must contain at least one byte");
+
+    let mut buf = Vec::new();
+    loop {
+        if reader.read_until(last, &mut buf).await? == 0 {
+            return Ok((!buf.is_empty()).then_some(buf));
+        }
+        if buf.ends_with(delimiter) {
+            buf.truncate(buf.len() - delimiter.len());
+            return Ok(Some(buf));
+        }
+    }
+}
+
+/**
+    Reads a single line, returning it without the trailing newline.
+*/
+pub async fn read_line<R>(reader: &mut R) -> io::Result<Option<Vec<u8>>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = read_until(reader, b"\n").await?;
+    if let Some(line) = line.as_mut().filter(|line| line.ends_with(b"\r")) {
+        line.pop();
+    }
+    Ok(line)
+}
+
+/**
+    Calls the given callback with a single line, in a new thread.
+
+    The callback may yield, and this will wait until it has finished running.
+*/
+pub async fn call_for_line(lua: &Lua, callback: &LuaFunction, line: Vec<u8>) -> LuaResult<()> {
+    // NOTE: Errors in threads are also reported by the scheduler, but any
+    // error here is returned to the caller instead, so we catch it in Lua
+    let pcall = lua.globals().get::<LuaFunction>("pcall")?;
+    let thread_id = lua.push_thread_back(pcall, (callback.clone(), BString::from(line)))?;
+    lua.track_thread(thread_id);
+    lua.wait_for_thread(thread_id).await;
+
+    let results = lua
+        .get_thread_result(thread_id)
+        .expect("Missing line callback thread result")?;
+    let mut results = results.into_iter();
+    match (results.next(), results.next()) {
+        (Some(LuaValue::Boolean(false)), Some(LuaValue::Error(e))) => Err(*e),
+        (Some(LuaValue::Boolean(false)), Some(value)) => Err(LuaError::runtime(value.to_string()?)),
+        _ => Ok(()),
+    }
+}
+
+/**
+    Reads all lines from the given reader, calling the callback for each one.
+*/
+pub async fn read_lines_with<R>(lua: &Lua, reader: &mut R, callback: &LuaFunction) -> LuaResult<()>
+where
+    R: AsyncBufRead + Unpin,
+{
+    while let Some(line) = read_line(reader).await.into_lua_err()? {
+        call_for_line(lua, callback, line).await?;
+    }
+    Ok(())
+}
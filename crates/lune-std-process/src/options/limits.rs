@@ -0,0 +1,77 @@
+use mlua::prelude::*;
+
+/**
+    Resource limits to apply to a child process before it starts running.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessSpawnOptionsLimits {
+    /// Maximum amount of CPU time, in seconds.
+    pub cpu: Option<u64>,
+    /// Maximum size of the virtual memory of the process, in bytes.
+    pub memory: Option<u64>,
+    /// Maximum number of open file descriptors.
+    pub files: Option<u64>,
+}
+
+impl ProcessSpawnOptionsLimits {
+    pub fn is_empty(&self) -> bool {
+        self.cpu.is_none() && self.memory.is_none() && self.files.is_none()
+    }
+
+    /**
+        Applies all of the limits to the current process.
+
+        This is called in the child process after forking and before exec,
+        and must therefore only do things that are async-signal-safe.
+    */
+    #[cfg(unix)]
+    pub fn apply(&self) -> std::io::Result<()> {
+        macro_rules! set_limit {
+            ($resource:expr, $value:expr) => {
+                if let Some(value) = $value {
+                    let limit = libc::rlimit {
+                        rlim_cur: value as libc::rlim_t,
+                        rlim_max: value as libc::rlim_t,
+                    };
+                    // SAFETY: setrlimit only reads the given limit, which lives on our stack
+                    if unsafe { libc::setrlimit($resource, &raw const limit) } != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+            };
+        }
+        set_limit!(libc::RLIMIT_CPU, self.cpu);
+        set_limit!(libc::RLIMIT_AS, self.memory);
+        set_limit!(libc::RLIMIT_NOFILE, self.files);
+        Ok(())
+    }
+}
+
+impl FromLua for ProcessSpawnOptionsLimits {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::Table(t) => {
+                let this = Self {
+                    cpu: t.get("cpu")?,
+                    memory: t.get("memory")?,
+                    files: t.get("files")?,
+                };
+                if cfg!(not(unix)) && !this.is_empty() {
+                    return Err(LuaError::runtime(
+                        "Invalid value for option 'limits' - resource limits are only supported on unix platforms",
+                    ));
+                }
+                Ok(this)
+            }
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ProcessSpawnOptionsLimits".to_string(),
+                message: Some(format!(
+                    "Invalid spawn options limits - expected table, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
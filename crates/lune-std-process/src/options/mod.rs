@@ -3,31 +3,56 @@ use std::{
     env::{self},
     ffi::OsString,
     path::PathBuf,
+    process::Command as StdCommand,
+    time::Duration,
 };
 
-use lune_utils::process::ProcessArgs;
+use lune_utils::process::{ProcessArgs, ProcessEnv};
 use mlua::prelude::*;
 
 use async_process::Command;
 use directories::UserDirs;
+use portable_pty::CommandBuilder;
+
+use crate::{env::current_env, which::ProgramLookup};
 
 mod kind;
+mod limits;
+mod pty;
 mod stdio;
 
 pub(super) use kind::*;
+pub(super) use limits::*;
+pub(super) use pty::*;
 pub(super) use stdio::*;
 
+// NOTE: Most programs need a terminal type to know which escape codes they can
+// use, and xterm is by far the most widely supported one, so we default to it
+const DEFAULT_PTY_TERM: &str = "xterm-256color";
+
 #[derive(Debug, Clone, Default)]
 pub(super) struct ProcessSpawnOptions {
     pub cwd: Option<PathBuf>,
+    pub base_env: Option<ProcessEnv>,
     pub envs: HashMap<String, String>,
     pub shell: Option<String>,
     pub stdio: ProcessSpawnOptionsStdio,
+    pub timeout: Option<Duration>,
+    pub process_group: bool,
+    pub limits: ProcessSpawnOptionsLimits,
+    pub pty: Option<ProcessSpawnOptionsPty>,
+    pub detached: bool,
 }
 
 impl FromLua for ProcessSpawnOptions {
-    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
-        let mut this = Self::default();
+    #[allow(clippy::too_many_lines)]
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        // NOTE: Children should see any changes made to process.env, which are
+        // not applied to the real environment, so we always pass it through
+        let mut this = Self {
+            base_env: current_env(lua)?,
+            ..Self::default()
+        };
         let value = match value {
             LuaValue::Nil => return Ok(this),
             LuaValue::Table(t) => t,
@@ -126,38 +151,221 @@ impl FromLua for ProcessSpawnOptions {
         */
         this.stdio = value.get("stdio")?;
 
+        /*
+            If we got a timeout, make sure it is a valid non-negative number of seconds
+        */
+        match value.get("timeout")? {
+            LuaValue::Nil => {}
+            LuaValue::Integer(i) => this.timeout = Some(parse_timeout(i as f64)?),
+            LuaValue::Number(n) => this.timeout = Some(parse_timeout(n)?),
+            value => {
+                return Err(LuaError::RuntimeError(format!(
+                    "Invalid type for option 'timeout' - expected number, got '{}'",
+                    value.type_name()
+                )));
+            }
+        }
+
+        /*
+            If we should spawn the process in its own process group, and any
+            resource limits to apply to it - these are only supported on unix
+        */
+        this.process_group = value
+            .get::<Option<bool>>("processGroup")?
+            .unwrap_or_default();
+        this.limits = value.get("limits")?;
+
+        /*
+            If we got options for a pseudo-terminal, the process will have it
+            as its controlling terminal, which it can not combine with limits
+        */
+        this.pty = match value.get("pty")? {
+            LuaValue::Nil | LuaValue::Boolean(false) => None,
+            value => Some(ProcessSpawnOptionsPty::from_lua(value, lua)?),
+        };
+        if this.pty.is_some() && !this.limits.is_empty() {
+            return Err(LuaError::runtime(
+                "Invalid value for option 'limits' - resource limits can not be used together with 'pty'",
+            ));
+        }
+
+        /*
+            If the process should be detached, it will run in its own session
+            and keep running after we exit, so it can not have a terminal
+        */
+        this.detached = value.get::<Option<bool>>("detached")?.unwrap_or_default();
+        if this.detached && this.pty.is_some() {
+            return Err(LuaError::runtime(
+                "Invalid value for option 'detached' - detached processes can not be used together with 'pty'",
+            ));
+        }
+
         Ok(this)
     }
 }
 
 impl ProcessSpawnOptions {
-    pub fn into_command(self, program: impl Into<OsString>, args: ProcessArgs) -> Command {
-        let mut program: OsString = program.into();
-        let mut args = args.into_iter().collect::<Vec<_>>();
+    /**
+        Returns the name of the first given option that is only
+        supported when running a process using `process.exec`.
+    */
+    pub fn exec_only_option(&self) -> Option<&'static str> {
+        if self.timeout.is_some() {
+            Some("timeout")
+        } else {
+            None
+        }
+    }
+
+    /**
+        Returns the name of the first given option that is only
+        supported when creating a process using `process.create`.
+    */
+    pub fn create_only_option(&self) -> Option<&'static str> {
+        if self.pty.is_some() {
+            Some("pty")
+        } else if self.detached {
+            Some("detached")
+        } else {
+            None
+        }
+    }
+
+    /**
+        Creates a lookup for the program that would be spawned
+        using these options, which is the shell, if one is used.
+    */
+    pub fn program_lookup(&self, program: &str) -> ProgramLookup {
+        ProgramLookup::new(
+            self.shell.as_deref().unwrap_or(program),
+            self.base_env.as_ref(),
+            &self.envs,
+            self.cwd.as_deref(),
+        )
+    }
+
+    fn program_and_args(
+        shell: Option<String>,
+        program: impl Into<OsString>,
+        args: ProcessArgs,
+    ) -> (OsString, Vec<OsString>) {
+        let program: OsString = program.into();
+        let args = args.into_iter().collect::<Vec<_>>();
 
         // Run a shell using the command param if wanted
-        if let Some(shell) = self.shell {
-            let mut shell_command = program.clone();
-            for arg in args {
-                shell_command.push(" ");
-                shell_command.push(arg);
+        match shell {
+            None => (program, args),
+            Some(shell) => {
+                let mut shell_command = program;
+                for arg in args {
+                    shell_command.push(" ");
+                    shell_command.push(arg);
+                }
+                (shell.into(), vec![OsString::from("-c"), shell_command])
             }
-            args = vec![OsString::from("-c"), shell_command];
-            program = shell.into();
         }
+    }
+
+    pub fn into_command(self, program: impl Into<OsString>, args: ProcessArgs) -> Command {
+        let (program, args) = Self::program_and_args(self.shell, program, args);
 
         // Create command with the wanted options
-        let mut cmd = Command::new(program);
+        let mut cmd = StdCommand::new(program);
         cmd.args(args);
 
         // Set dir to run in and env variables
         if let Some(cwd) = self.cwd {
             cmd.current_dir(cwd);
         }
+        if let Some(base_env) = self.base_env {
+            cmd.env_clear();
+            cmd.envs(base_env);
+        }
         if !self.envs.is_empty() {
             cmd.envs(self.envs);
         }
 
-        cmd
+        // Set session or process group and resource limits, which must happen before exec
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            if self.detached {
+                // NOTE: A new session also creates a new process group, and setsid
+                // would fail if the process was already made a process group leader
+                // SAFETY: setsid is async-signal-safe and does not touch any memory
+                unsafe {
+                    cmd.pre_exec(|| {
+                        if libc::setsid() == -1 {
+                            Err(std::io::Error::last_os_error())
+                        } else {
+                            Ok(())
+                        }
+                    });
+                }
+            } else if self.process_group {
+                cmd.process_group(0);
+            }
+            if !self.limits.is_empty() {
+                let limits = self.limits;
+                // SAFETY: Applying limits only calls setrlimit, which is async-signal-safe
+                unsafe {
+                    cmd.pre_exec(move || limits.apply());
+                }
+            }
+        }
+
+        // Detach from the console, and make sure that
+        // we never forward any ctrl+c events to the process
+        #[cfg(windows)]
+        if self.detached {
+            use std::os::windows::process::CommandExt;
+            const DETACHED_PROCESS: u32 = 0x0000_0008;
+            const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+            cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+        }
+
+        Command::from(cmd)
     }
+
+    pub fn into_pty_command(
+        self,
+        program: impl Into<OsString>,
+        args: ProcessArgs,
+    ) -> LuaResult<CommandBuilder> {
+        let (program, args) = Self::program_and_args(self.shell, program, args);
+
+        let mut cmd = CommandBuilder::new(program);
+        cmd.args(args);
+
+        // NOTE: Processes spawned in a pty default to running in the home
+        // directory, so we always need to set the directory to run in here
+        let cwd = match self.cwd {
+            Some(cwd) => cwd,
+            None => env::current_dir()?,
+        };
+        cmd.cwd(cwd);
+
+        if let Some(base_env) = self.base_env {
+            cmd.env_clear();
+            for (key, value) in base_env {
+                cmd.env(key, value);
+            }
+        }
+        if cmd.get_env("TERM").is_none() {
+            cmd.env("TERM", DEFAULT_PTY_TERM);
+        }
+        for (key, value) in self.envs {
+            cmd.env(key, value);
+        }
+
+        Ok(cmd)
+    }
+}
+
+fn parse_timeout(secs: f64) -> LuaResult<Duration> {
+    Duration::try_from_secs_f64(secs).map_err(|_| {
+        LuaError::RuntimeError(format!(
+            "Invalid value for option 'timeout' - expected a non-negative number of seconds, got {secs}"
+        ))
+    })
 }
@@ -0,0 +1,63 @@
+use mlua::prelude::*;
+use portable_pty::PtySize;
+
+const DEFAULT_COLS: u16 = 80;
+const DEFAULT_ROWS: u16 = 24;
+
+/**
+    Options for spawning a child process attached to a pseudo-terminal.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessSpawnOptionsPty {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl Default for ProcessSpawnOptionsPty {
+    fn default() -> Self {
+        Self {
+            cols: DEFAULT_COLS,
+            rows: DEFAULT_ROWS,
+        }
+    }
+}
+
+impl ProcessSpawnOptionsPty {
+    pub fn size(self) -> PtySize {
+        PtySize {
+            cols: self.cols,
+            rows: self.rows,
+            pixel_width: 0,
+            pixel_height: 0,
+        }
+    }
+}
+
+impl FromLua for ProcessSpawnOptionsPty {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Boolean(true) => Ok(Self::default()),
+            LuaValue::Table(t) => {
+                let defaults = Self::default();
+                let this = Self {
+                    cols: t.get::<Option<u16>>("cols")?.unwrap_or(defaults.cols),
+                    rows: t.get::<Option<u16>>("rows")?.unwrap_or(defaults.rows),
+                };
+                if this.cols == 0 || this.rows == 0 {
+                    return Err(LuaError::runtime(
+                        "Invalid value for option 'pty' - cols and rows must be greater than zero",
+                    ));
+                }
+                Ok(this)
+            }
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ProcessSpawnOptionsPty".to_string(),
+                message: Some(format!(
+                    "Invalid spawn options pty - expected boolean or table, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
@@ -8,6 +8,8 @@ pub struct ProcessSpawnOptionsStdio {
     pub stdout: ProcessSpawnOptionsStdioKind,
     pub stderr: ProcessSpawnOptionsStdioKind,
     pub stdin: Option<Vec<u8>>,
+    pub stdout_callback: Option<LuaFunction>,
+    pub stderr_callback: Option<LuaFunction>,
 }
 
 impl From<ProcessSpawnOptionsStdioKind> for ProcessSpawnOptionsStdio {
@@ -34,12 +36,16 @@ impl FromLua for ProcessSpawnOptionsStdio {
                     this.stdin = Some(stdin.to_vec());
                 }
 
-                if let Some(stdout) = t.get("stdout")? {
-                    this.stdout = stdout;
+                // Output streams may also be given as callbacks, which
+                // receive output line-by-line instead of capturing it
+                match t.get("stdout")? {
+                    LuaValue::Function(f) => this.stdout_callback = Some(f),
+                    value => this.stdout = ProcessSpawnOptionsStdioKind::from_lua(value, lua)?,
                 }
 
-                if let Some(stderr) = t.get("stderr")? {
-                    this.stderr = stderr;
+                match t.get("stderr")? {
+                    LuaValue::Function(f) => this.stderr_callback = Some(f),
+                    value => this.stderr = ProcessSpawnOptionsStdioKind::from_lua(value, lua)?,
                 }
 
                 Ok(this)
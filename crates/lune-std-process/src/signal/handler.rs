@@ -0,0 +1,252 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    io,
+    sync::{
+        Arc, LazyLock, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+
+use async_channel::{Receiver, Sender, unbounded};
+use async_signal::{Signal, Signals};
+use futures_lite::{future::block_on, prelude::*};
+
+use mlua::prelude::*;
+use mlua_luau_scheduler::{LuaSchedulerExt, LuaSpawnExt};
+
+use super::ProcessSignal;
+
+/*
+    NOTE: Signal handlers are global to the whole process, and once a
+    handler has been registered it can never be fully removed again, so
+    each signal gets a single listener thread that lives forever.
+
+    The listener forwards signals to all of the Lua states that currently
+    have handlers for it, and when there are none, it emulates the default
+    behavior for the signal instead - which is usually to terminate.
+*/
+static SUBSCRIBERS: LazyLock<Mutex<HashMap<ProcessSignal, Vec<Sender<()>>>>> =
+    LazyLock::new(Mutex::default);
+
+fn subscribe(signal: ProcessSignal) -> io::Result<Receiver<()>> {
+    let mut subscribers = SUBSCRIBERS
+        .lock()
+        .expect("signal subscribers lock was poisoned");
+    let list = match subscribers.entry(signal) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            start_listener(signal)?;
+            entry.insert(Vec::new())
+        }
+    };
+
+    let (tx, rx) = unbounded();
+    list.push(tx);
+    Ok(rx)
+}
+
+fn start_listener(signal: ProcessSignal) -> io::Result<()> {
+    let mut signals = Signals::new([listenable_signal(signal)?])?;
+    thread::Builder::new()
+        .name(format!(
+            "lune-signal-{}",
+            signal.name().to_ascii_lowercase()
+        ))
+        .spawn(move || {
+            while let Some(Ok(_)) = block_on(signals.next()) {
+                dispatch(signal);
+            }
+        })?;
+    Ok(())
+}
+
+fn dispatch(signal: ProcessSignal) {
+    let mut subscribers = SUBSCRIBERS
+        .lock()
+        .expect("signal subscribers lock was poisoned");
+    let list = subscribers.entry(signal).or_default();
+    // Sending will only fail if the subscriber has removed all of its handlers
+    list.retain(|tx| tx.try_send(()).is_ok());
+    if list.is_empty() {
+        drop(subscribers);
+        emulate_default(signal);
+    }
+}
+
+#[cfg(unix)]
+fn listenable_signal(signal: ProcessSignal) -> io::Result<Signal> {
+    Ok(match signal {
+        ProcessSignal::Hup => Signal::Hup,
+        ProcessSignal::Int => Signal::Int,
+        ProcessSignal::Quit => Signal::Quit,
+        ProcessSignal::Term => Signal::Term,
+        ProcessSignal::Usr1 => Signal::Usr1,
+        ProcessSignal::Usr2 => Signal::Usr2,
+        ProcessSignal::Winch => Signal::Winch,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "Handling {signal} is not supported, supported signals are:\n\
+                    HUP, INT, QUIT, TERM, USR1, USR2, WINCH"
+                ),
+            ));
+        }
+    })
+}
+
+#[cfg(not(unix))]
+fn listenable_signal(signal: ProcessSignal) -> io::Result<Signal> {
+    if signal == ProcessSignal::Int {
+        Ok(Signal::Int)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Handling {signal} is only supported on unix platforms"),
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn emulate_default(signal: ProcessSignal) {
+    // The default action for window size changes is to do nothing
+    if signal == ProcessSignal::Winch {
+        return;
+    }
+    let raw = signal.as_raw();
+    // SAFETY: Restoring the default action and raising the signal again
+    // does not touch any memory, and will terminate the process
+    unsafe {
+        libc::signal(raw, libc::SIG_DFL);
+        libc::raise(raw);
+    }
+    // NOTE: Should be unreachable, but if the signal was somehow
+    // blocked, exit with the same code that a shell would report
+    std::process::exit(128 + raw);
+}
+
+#[cfg(windows)]
+fn emulate_default(_: ProcessSignal) {
+    use windows_sys::Win32::Foundation::STATUS_CONTROL_C_EXIT;
+    std::process::exit(STATUS_CONTROL_C_EXIT);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn emulate_default(_: ProcessSignal) {}
+
+// Handlers for a single Lua state
+
+#[derive(Debug, Default)]
+struct SignalHandlers {
+    next_id: u64,
+    signals: HashMap<ProcessSignal, SignalHandlerList>,
+}
+
+#[derive(Debug)]
+struct SignalHandlerList {
+    receiver: Receiver<()>,
+    handlers: Vec<(u64, LuaFunction)>,
+}
+
+/**
+    A handle to a signal handler registered using `process.onSignal`,
+    which can be used to disconnect the handler once it is no longer needed.
+*/
+#[derive(Debug, Clone)]
+pub struct SignalConnection {
+    id: u64,
+    signal: ProcessSignal,
+    connected: Arc<AtomicBool>,
+}
+
+impl SignalConnection {
+    fn disconnect(&self, lua: &Lua) {
+        let mut state = lua
+            .app_data_mut::<SignalHandlers>()
+            .expect("signal handlers must exist for a connection");
+        let Some(list) = state.signals.get_mut(&self.signal) else {
+            return;
+        };
+        list.handlers.retain(|(id, _)| *id != self.id);
+        if list.handlers.is_empty() {
+            // NOTE: Closing the channel stops our dispatcher task, and the
+            // signal listener will then stop sending signals to this state
+            list.receiver.close();
+            state.signals.remove(&self.signal);
+        }
+    }
+}
+
+impl LuaUserData for SignalConnection {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("connected", |_, this| {
+            Ok(this.connected.load(Ordering::SeqCst))
+        });
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("disconnect", |lua, this, ()| {
+            if this.connected.swap(false, Ordering::SeqCst) {
+                this.disconnect(lua);
+                Ok(())
+            } else {
+                Err(LuaError::runtime("Signal handler already disconnected"))
+            }
+        });
+    }
+}
+
+/**
+    Registers a Lua function to be called whenever the given signal is received.
+
+    The first handler registered for a signal starts a task that will keep the
+    scheduler running, until all of the handlers for that signal are disconnected.
+*/
+pub fn on_signal(
+    lua: &Lua,
+    (signal, handler): (ProcessSignal, LuaFunction),
+) -> LuaResult<SignalConnection> {
+    if lua.app_data_ref::<SignalHandlers>().is_none() {
+        lua.set_app_data(SignalHandlers::default());
+    }
+
+    let mut state = lua
+        .app_data_mut::<SignalHandlers>()
+        .expect("signal handlers were created above");
+    let id = state.next_id;
+    state.next_id += 1;
+
+    if let Some(list) = state.signals.get_mut(&signal) {
+        list.handlers.push((id, handler));
+    } else {
+        let receiver = subscribe(signal).into_lua_err()?;
+        lua.spawn_local(dispatch_to_handlers(lua.clone(), signal, receiver.clone()));
+        state.signals.insert(
+            signal,
+            SignalHandlerList {
+                receiver,
+                handlers: vec![(id, handler)],
+            },
+        );
+    }
+
+    Ok(SignalConnection {
+        id,
+        signal,
+        connected: Arc::new(AtomicBool::new(true)),
+    })
+}
+
+async fn dispatch_to_handlers(lua: Lua, signal: ProcessSignal, receiver: Receiver<()>) {
+    while receiver.recv().await.is_ok() {
+        let handlers = lua
+            .app_data_ref::<SignalHandlers>()
+            .and_then(|state| state.signals.get(&signal).map(|list| list.handlers.clone()))
+            .unwrap_or_default();
+        for (_, handler) in handlers {
+            // NOTE: Errors in handlers are reported by the scheduler
+            let _ = lua.push_thread_back(handler, signal.name());
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::{fmt, io};
+
+use async_process::Child;
+use mlua::prelude::*;
+
+mod handler;
+
+pub use self::handler::on_signal;
+
+/**
+    A signal that can be sent to a process.
+
+    Only the signals that are commonly available on all unix platforms are supported.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProcessSignal {
+    Hup,
+    Int,
+    Quit,
+    Abrt,
+    Kill,
+    Usr1,
+    Usr2,
+    Pipe,
+    Alrm,
+    Term,
+    Chld,
+    Cont,
+    Stop,
+    Tstp,
+    Winch,
+}
+
+impl ProcessSignal {
+    pub const ALL: [Self; 15] = [
+        Self::Hup,
+        Self::Int,
+        Self::Quit,
+        Self::Abrt,
+        Self::Kill,
+        Self::Usr1,
+        Self::Usr2,
+        Self::Pipe,
+        Self::Alrm,
+        Self::Term,
+        Self::Chld,
+        Self::Cont,
+        Self::Stop,
+        Self::Tstp,
+        Self::Winch,
+    ];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Hup => "HUP",
+            Self::Int => "INT",
+            Self::Quit => "QUIT",
+            Self::Abrt => "ABRT",
+            Self::Kill => "KILL",
+            Self::Usr1 => "USR1",
+            Self::Usr2 => "USR2",
+            Self::Pipe => "PIPE",
+            Self::Alrm => "ALRM",
+            Self::Term => "TERM",
+            Self::Chld => "CHLD",
+            Self::Cont => "CONT",
+            Self::Stop => "STOP",
+            Self::Tstp => "TSTP",
+            Self::Winch => "WINCH",
+        }
+    }
+
+    #[cfg(unix)]
+    #[must_use]
+    pub const fn as_raw(self) -> libc::c_int {
+        match self {
+            Self::Hup => libc::SIGHUP,
+            Self::Int => libc::SIGINT,
+            Self::Quit => libc::SIGQUIT,
+            Self::Abrt => libc::SIGABRT,
+            Self::Kill => libc::SIGKILL,
+            Self::Usr1 => libc::SIGUSR1,
+            Self::Usr2 => libc::SIGUSR2,
+            Self::Pipe => libc::SIGPIPE,
+            Self::Alrm => libc::SIGALRM,
+            Self::Term => libc::SIGTERM,
+            Self::Chld => libc::SIGCHLD,
+            Self::Cont => libc::SIGCONT,
+            Self::Stop => libc::SIGSTOP,
+            Self::Tstp => libc::SIGTSTP,
+            Self::Winch => libc::SIGWINCH,
+        }
+    }
+}
+
+impl fmt::Display for ProcessSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SIG{}", self.name())
+    }
+}
+
+impl FromLua for ProcessSignal {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let LuaValue::String(s) = &value else {
+            return Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ProcessSignal".to_string(),
+                message: Some(format!(
+                    "Invalid signal - expected string, got {}",
+                    value.type_name()
+                )),
+            });
+        };
+        // Accept any casing, with or without the "SIG" prefix
+        let name = s.to_string_lossy().trim().to_ascii_uppercase();
+        let stripped = name.strip_prefix("SIG").unwrap_or(&name);
+        Self::ALL
+            .into_iter()
+            .find(|signal| signal.name() == stripped)
+            .ok_or_else(|| {
+                LuaError::RuntimeError(format!(
+                    "Invalid signal '{name}', valid signals are:\n{}",
+                    Self::ALL
+                        .into_iter()
+                        .map(Self::name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            })
+    }
+}
+
+/**
+    Sends the given signal to the process with the given id, or
+    to its whole process group, if `group` is set to `true`.
+
+    # Errors
+
+    Errors if the signal could not be sent, or if the current platform is not unix.
+*/
+#[cfg(unix)]
+pub fn send_signal(pid: u32, signal: ProcessSignal, group: bool) -> io::Result<()> {
+    let pid = pid as libc::pid_t;
+    let target = if group { -pid } else { pid };
+    // SAFETY: kill does not touch any memory, and an invalid
+    // target is reported as an error instead of being undefined
+    if unsafe { libc::kill(target, signal.as_raw()) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(windows)]
+pub fn send_signal(pid: u32, signal: ProcessSignal, _: bool) -> io::Result<()> {
+    use windows_sys::Win32::{
+        Foundation::CloseHandle,
+        System::Threading::{OpenProcess, PROCESS_TERMINATE, TerminateProcess},
+    };
+
+    if signal != ProcessSignal::Kill {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Sending {signal} is only supported on unix platforms"),
+        ));
+    }
+
+    // SAFETY: The handle is checked for validity and always closed after use
+    unsafe {
+        let handle = OpenProcess(PROCESS_TERMINATE, 0, pid);
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let result = if TerminateProcess(handle, 1) == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        };
+        CloseHandle(handle);
+        result
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn send_signal(_: u32, signal: ProcessSignal, _: bool) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Sending {signal} is only supported on unix platforms"),
+    ))
+}
+
+/**
+    Checks if a process with the given id is currently running.
+
+    Note that a process which has exited but has not yet been waited
+    on by its parent process will still be considered to be running.
+*/
+#[cfg(unix)]
+pub fn is_running(pid: u32) -> bool {
+    // SAFETY: Sending signal 0 only checks if the process exists and
+    // if we would have permission to signal it, nothing is delivered
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+pub fn is_running(pid: u32) -> bool {
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, STILL_ACTIVE},
+        System::Threading::{GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION},
+    };
+
+    // SAFETY: The handle is checked for validity and always closed after use
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return false;
+        }
+        let mut code = 0;
+        let running = GetExitCodeProcess(handle, &raw mut code) != 0 && code == STILL_ACTIVE as u32;
+        CloseHandle(handle);
+        running
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn is_running(_: u32) -> bool {
+    false
+}
+
+/**
+    Parses a process id given from Lua, making sure it refers to a single process.
+*/
+pub fn parse_pid(pid: LuaNumber) -> LuaResult<u32> {
+    // NOTE: Zero and negative ids refer to process groups, or even all
+    // processes, when sending signals on unix, so we must never allow them
+    if pid.fract() == 0.0 && pid >= 1.0 && pid <= f64::from(i32::MAX) {
+        Ok(pid as u32)
+    } else {
+        Err(LuaError::RuntimeError(format!(
+            "Invalid process id '{pid}' - expected a positive integer"
+        )))
+    }
+}
+
+/**
+    Kills the given child process, and all other processes
+    in its process group, if `group` is set to `true`.
+*/
+pub fn kill_child(child: &mut Child, group: bool) {
+    if group {
+        let _ = send_signal(child.id(), ProcessSignal::Kill, true);
+    }
+    let _ = child.kill(); // Will only error if already killed
+}
@@ -0,0 +1,105 @@
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use mlua::prelude::*;
+
+use lune_utils::TableBuilder;
+
+static START_TIME: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/**
+    Marks the start of the current process, if not already marked.
+
+    This should be called as early as possible, since
+    uptime is measured from the first time it is called.
+*/
+pub fn mark_start() {
+    LazyLock::force(&START_TIME);
+}
+
+pub fn process_uptime(_: &Lua, (): ()) -> LuaResult<f64> {
+    Ok(START_TIME.elapsed().as_secs_f64())
+}
+
+pub fn process_memory_usage(lua: &Lua, (): ()) -> LuaResult<LuaTable> {
+    let stats = memory_stats::memory_stats()
+        .ok_or_else(|| LuaError::runtime("Failed to get memory usage of the current process"))?;
+    TableBuilder::new(lua.clone())?
+        .with_value("rss", stats.physical_mem)?
+        .with_value("virtual", stats.virtual_mem)?
+        .with_value("heap", lua.used_memory())?
+        .build_readonly()
+}
+
+pub fn process_cpu_usage(lua: &Lua, (): ()) -> LuaResult<LuaTable> {
+    let (user, system) = cpu_times()?;
+    TableBuilder::new(lua.clone())?
+        .with_value("user", user.as_secs_f64())?
+        .with_value("system", system.as_secs_f64())?
+        .build_readonly()
+}
+
+/**
+    Gets the total time spent by the current process in user and system mode, respectively.
+*/
+#[cfg(unix)]
+fn cpu_times() -> LuaResult<(Duration, Duration)> {
+    fn to_duration(time: libc::timeval) -> Duration {
+        Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+    }
+
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: getrusage fully initializes the given struct when it succeeds
+    let usage = unsafe {
+        if libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error().into_lua_err());
+        }
+        usage.assume_init()
+    };
+
+    Ok((to_duration(usage.ru_utime), to_duration(usage.ru_stime)))
+}
+
+#[cfg(windows)]
+fn cpu_times() -> LuaResult<(Duration, Duration)> {
+    use windows_sys::Win32::{
+        Foundation::FILETIME,
+        System::Threading::{GetCurrentProcess, GetProcessTimes},
+    };
+
+    // NOTE: File times are given in units of 100 nanoseconds
+    fn to_duration(time: FILETIME) -> Duration {
+        let ticks = (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime);
+        Duration::from_nanos(ticks * 100)
+    }
+
+    let mut creation = FILETIME::default();
+    let mut exit = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+    // SAFETY: The current process handle is a pseudo handle that is always
+    // valid and does not need to be closed, and all pointers are valid
+    let result = unsafe {
+        GetProcessTimes(
+            GetCurrentProcess(),
+            &raw mut creation,
+            &raw mut exit,
+            &raw mut kernel,
+            &raw mut user,
+        )
+    };
+    if result == 0 {
+        return Err(std::io::Error::last_os_error().into_lua_err());
+    }
+
+    Ok((to_duration(user), to_duration(kernel)))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn cpu_times() -> LuaResult<(Duration, Duration)> {
+    Err(LuaError::runtime(
+        "Getting cpu usage is not supported on the current platform",
+    ))
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    env::split_paths,
+    error::Error,
+    ffi::{OsStr, OsString},
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use mlua::prelude::*;
+
+use lune_utils::{path::get_current_dir, process::ProcessEnv};
+
+use crate::env::current_env;
+
+#[cfg(windows)]
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/**
+    Error returned when a program could not be found, containing
+    all of the paths that were searched while looking for it.
+*/
+#[derive(Debug, Clone)]
+pub struct ProgramNotFound {
+    pub program: OsString,
+    pub searched: Vec<PathBuf>,
+}
+
+impl fmt::Display for ProgramNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let program = self.program.to_string_lossy();
+        if self.searched.is_empty() {
+            return write!(f, "Program '{program}' was not found - PATH is empty");
+        }
+        write!(f, "Program '{program}' was not found, searched:")?;
+        for path in &self.searched {
+            write!(f, "\n    {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ProgramNotFound {}
+
+/**
+    Looks up programs the same way that spawning them would,
+    using the `PATH` (and `PATHEXT`, on Windows) of a given env.
+*/
+#[derive(Debug, Clone)]
+pub struct ProgramLookup {
+    program: OsString,
+    path: Option<OsString>,
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pathext: Option<OsString>,
+    cwd: PathBuf,
+}
+
+impl ProgramLookup {
+    /**
+        Creates a new lookup for the given program, where any
+        variables in `overrides` take precedence over the base env.
+
+        Relative paths are resolved against the given directory, or the
+        current working directory of the Lune process if not given.
+    */
+    pub fn new(
+        program: impl Into<OsString>,
+        env: Option<&ProcessEnv>,
+        overrides: &HashMap<String, String>,
+        cwd: Option<&Path>,
+    ) -> Self {
+        let var = |key: &str| {
+            overrides
+                .iter()
+                .find(|(k, _)| env_key_eq(OsStr::new(k), key))
+                .map(|(_, v)| OsString::from(v))
+                .or_else(|| {
+                    env?.get_all()
+                        .into_iter()
+                        .find(|(k, _)| env_key_eq(k, key))
+                        .map(|(_, v)| v)
+                })
+        };
+        let cwd = get_current_dir().join(cwd.unwrap_or(Path::new("")));
+        Self {
+            program: program.into(),
+            path: var("PATH"),
+            pathext: var("PATHEXT"),
+            cwd,
+        }
+    }
+
+    /**
+        Finds the full path to the program.
+
+        Programs that contain a path separator are never searched for
+        in `PATH`, and are instead resolved relative to the directory.
+    */
+    pub fn find(&self) -> Result<PathBuf, ProgramNotFound> {
+        let program = Path::new(&self.program);
+        let dirs = if program.components().count() > 1 || program.is_absolute() {
+            vec![self.cwd.clone()]
+        } else {
+            self.path
+                .as_deref()
+                .map(|path| {
+                    split_paths(path)
+                        .filter(|dir| !dir.as_os_str().is_empty())
+                        .map(|dir| self.cwd.join(dir))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        for dir in &dirs {
+            for candidate in self.candidates(&dir.join(program)) {
+                if is_executable(&candidate) {
+                    return Ok(candidate);
+                }
+            }
+        }
+
+        Err(ProgramNotFound {
+            program: self.program.clone(),
+            searched: dirs,
+        })
+    }
+
+    /**
+        Converts an error from spawning the program into a Lua error,
+        replacing it with a [`ProgramNotFound`] error if the program
+        could not be found, since the original error would not say where.
+    */
+    pub fn spawn_error(&self, err: io::Error) -> LuaError {
+        match self.find() {
+            Err(not_found) if err.kind() == io::ErrorKind::NotFound => {
+                LuaError::external(not_found)
+            }
+            _ => LuaError::external(err),
+        }
+    }
+
+    #[cfg(windows)]
+    fn candidates(&self, path: &Path) -> Vec<PathBuf> {
+        let pathext = self
+            .pathext
+            .as_deref()
+            .unwrap_or(OsStr::new(DEFAULT_PATHEXT));
+        let exts = pathext
+            .to_string_lossy()
+            .split(';')
+            .filter(|ext| !ext.is_empty())
+            .map(str::to_ascii_lowercase)
+            .collect::<Vec<_>>();
+
+        // Programs that already have an executable extension are used
+        // as they are, all others get each of the extensions appended
+        let has_ext = path.extension().is_some_and(|ext| {
+            let ext = format!(".{}", ext.to_string_lossy().to_ascii_lowercase());
+            exts.contains(&ext)
+        });
+        if has_ext {
+            return vec![path.to_path_buf()];
+        }
+
+        exts.into_iter()
+            .map(|ext| {
+                let mut candidate = path.as_os_str().to_os_string();
+                candidate.push(ext);
+                PathBuf::from(candidate)
+            })
+            .collect()
+    }
+
+    #[cfg(not(windows))]
+    #[allow(clippy::unused_self)]
+    fn candidates(&self, path: &Path) -> Vec<PathBuf> {
+        vec![path.to_path_buf()]
+    }
+}
+
+#[cfg(windows)]
+fn env_key_eq(key: &OsStr, name: &str) -> bool {
+    key.eq_ignore_ascii_case(name)
+}
+
+#[cfg(not(windows))]
+fn env_key_eq(key: &OsStr, name: &str) -> bool {
+    key == name
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+pub fn process_which(lua: &Lua, program: String) -> LuaResult<Option<String>> {
+    let env = current_env(lua)?;
+    let lookup = ProgramLookup::new(program, env.as_ref(), &HashMap::new(), None);
+    Ok(lookup
+        .find()
+        .ok()
+        .map(|path| path.to_string_lossy().into_owned()))
+}
@@ -15,6 +15,10 @@ use async_compression::{
     },
 };
 
+mod stream;
+
+pub use self::stream::{Compressor, Decompressor};
+
 /**
     A compression and decompression format supported by Lune.
 */
@@ -0,0 +1,424 @@
+use std::{
+    cell::RefCell,
+    io::{Error, Read, Write},
+    mem,
+    rc::Rc,
+};
+
+use async_compression::{
+    Level,
+    futures::write::{
+        BrotliDecoder, BrotliEncoder, GzipDecoder, GzipEncoder, ZlibDecoder, ZlibEncoder,
+        ZstdDecoder, ZstdEncoder,
+    },
+};
+use async_lock::Mutex as AsyncMutex;
+use bstr::BString;
+use futures_lite::prelude::*;
+use lz4::{Decoder as Lz4Decoder, Encoder as Lz4Encoder, EncoderBuilder as Lz4EncoderBuilder};
+use mlua::prelude::*;
+
+use super::CompressDecompressFormat;
+
+// NOTE: The least amount of bytes needed to detect any format, see `detect_from_bytes`
+const DETECT_MIN_BYTES: usize = 4;
+
+// NOTE: The lz4 format used by serde starts with the decompressed size, see `compress_lz4`
+const LZ4_SIZE_PREFIX_BYTES: usize = 4;
+
+/**
+    A writer that can be shared with an encoder that only
+    gives out a shared reference to its inner writer.
+
+    Also usable as a reader that can be shared with a decoder,
+    where reading consumes bytes from the start of the buffer.
+*/
+#[derive(Debug, Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take_bytes(&self) -> Vec<u8> {
+        mem::take(&mut self.0.borrow_mut())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Read for SharedBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut inner = self.0.borrow_mut();
+        let len = buf.len().min(inner.len());
+        buf[..len].copy_from_slice(&inner[..len]);
+        inner.drain(..len);
+        Ok(len)
+    }
+}
+
+/**
+    An incremental lz4 decoder, which is given bytes as they arrive.
+
+    The lz4 crate only supports pull-based decoding, but its decoder stops and
+    returns zero bytes whenever there is no more input, while keeping track
+    of where it is in the stream, so it can be resumed once given more input.
+*/
+struct Lz4StreamDecoder {
+    prefix_remaining: usize,
+    input: SharedBuffer,
+    decoder: Lz4Decoder<SharedBuffer>,
+}
+
+impl Lz4StreamDecoder {
+    fn new() -> Result<Self, Error> {
+        let input = SharedBuffer::default();
+        Ok(Self {
+            prefix_remaining: LZ4_SIZE_PREFIX_BYTES,
+            decoder: Lz4Decoder::new(input.clone())?,
+            input,
+        })
+    }
+
+    fn update(&mut self, chunk: &[u8]) -> Result<Vec<u8>, Error> {
+        let skipped = self.prefix_remaining.min(chunk.len());
+        self.prefix_remaining -= skipped;
+        self.input.write_all(&chunk[skipped..])?;
+
+        let mut output = Vec::new();
+        self.decoder.read_to_end(&mut output)?;
+        Ok(output)
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>, Error> {
+        let output = self.update(&[])?;
+        let (_, result) = self.decoder.finish();
+        result.map_err(|_| Error::other("Unexpected end of lz4 stream"))?;
+        Ok(output)
+    }
+}
+
+// Inner (plumbing) implementations
+
+enum CompressorInner {
+    Brotli(Box<BrotliEncoder<Vec<u8>>>),
+    GZip(GzipEncoder<Vec<u8>>),
+    ZLib(ZlibEncoder<Vec<u8>>),
+    Zstd(ZstdEncoder<Vec<u8>>),
+    LZ4(Lz4Encoder<SharedBuffer>, SharedBuffer),
+}
+
+impl CompressorInner {
+    fn new(format: CompressDecompressFormat, level: Option<i32>) -> Result<Self, Error> {
+        let quality = match level {
+            Some(l) => Level::Precise(l),
+            None => Level::Best,
+        };
+        Ok(match format {
+            CompressDecompressFormat::Brotli => {
+                Self::Brotli(Box::new(BrotliEncoder::with_quality(Vec::new(), quality)))
+            }
+            CompressDecompressFormat::GZip => {
+                Self::GZip(GzipEncoder::with_quality(Vec::new(), quality))
+            }
+            CompressDecompressFormat::ZLib => {
+                Self::ZLib(ZlibEncoder::with_quality(Vec::new(), quality))
+            }
+            CompressDecompressFormat::Zstd => {
+                Self::Zstd(ZstdEncoder::with_quality(Vec::new(), quality))
+            }
+            CompressDecompressFormat::LZ4 => {
+                // The total size is not known up front, but it is only ever used as a
+                // capacity hint when decompressing, so a zero size is still compatible
+                let mut buffer = SharedBuffer::default();
+                buffer.write_all(&0u32.to_le_bytes())?;
+                let encoder = Lz4EncoderBuilder::new()
+                    .level(16)
+                    .checksum(lz4::ContentChecksum::ChecksumEnabled)
+                    .block_mode(lz4::BlockMode::Independent)
+                    .build(buffer.clone())?;
+                Self::LZ4(encoder, buffer)
+            }
+        })
+    }
+
+    async fn update(&mut self, chunk: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Brotli(e) => {
+                e.write_all(chunk).await?;
+                Ok(mem::take(e.get_mut()))
+            }
+            Self::GZip(e) => {
+                e.write_all(chunk).await?;
+                Ok(mem::take(e.get_mut()))
+            }
+            Self::ZLib(e) => {
+                e.write_all(chunk).await?;
+                Ok(mem::take(e.get_mut()))
+            }
+            Self::Zstd(e) => {
+                e.write_all(chunk).await?;
+                Ok(mem::take(e.get_mut()))
+            }
+            Self::LZ4(e, buffer) => {
+                e.write_all(chunk)?;
+                Ok(buffer.take_bytes())
+            }
+        }
+    }
+
+    async fn finish(self) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Brotli(mut e) => {
+                e.close().await?;
+                Ok(e.into_inner())
+            }
+            Self::GZip(mut e) => {
+                e.close().await?;
+                Ok(e.into_inner())
+            }
+            Self::ZLib(mut e) => {
+                e.close().await?;
+                Ok(e.into_inner())
+            }
+            Self::Zstd(mut e) => {
+                e.close().await?;
+                Ok(e.into_inner())
+            }
+            Self::LZ4(e, buffer) => {
+                let (_, result) = e.finish();
+                result?;
+                Ok(buffer.take_bytes())
+            }
+        }
+    }
+}
+
+enum DecompressorInner {
+    // The format has not yet been detected, and bytes are held back until it can be
+    Detecting(Vec<u8>),
+    Brotli(Box<BrotliDecoder<Vec<u8>>>),
+    GZip(GzipDecoder<Vec<u8>>),
+    ZLib(ZlibDecoder<Vec<u8>>),
+    Zstd(ZstdDecoder<Vec<u8>>),
+    LZ4(Box<Lz4StreamDecoder>),
+}
+
+impl DecompressorInner {
+    fn new(format: Option<CompressDecompressFormat>) -> Result<Self, Error> {
+        Ok(match format {
+            None => Self::Detecting(Vec::new()),
+            Some(CompressDecompressFormat::Brotli) => {
+                Self::Brotli(Box::new(BrotliDecoder::new(Vec::new())))
+            }
+            Some(CompressDecompressFormat::GZip) => Self::GZip(GzipDecoder::new(Vec::new())),
+            Some(CompressDecompressFormat::ZLib) => Self::ZLib(ZlibDecoder::new(Vec::new())),
+            Some(CompressDecompressFormat::Zstd) => Self::Zstd(ZstdDecoder::new(Vec::new())),
+            Some(CompressDecompressFormat::LZ4) => Self::LZ4(Box::new(Lz4StreamDecoder::new()?)),
+        })
+    }
+
+    /**
+        Tries to detect the format from the bytes held back so far,
+        replacing this decompressor with one for the detected format.
+
+        Returns the held back bytes, which must then be passed to `update`.
+    */
+    fn detect(&mut self, force: bool) -> Result<Option<Vec<u8>>, Error> {
+        let Self::Detecting(pending) = self else {
+            return Ok(None);
+        };
+        if pending.len() < DETECT_MIN_BYTES && !force {
+            return Ok(None);
+        }
+        let Some(format) = CompressDecompressFormat::detect_from_bytes(&pending) else {
+            return Err(Error::other(
+                "Failed to detect compression format from the given bytes",
+            ));
+        };
+        let pending = mem::take(pending);
+        *self = Self::new(Some(format))?;
+        Ok(Some(pending))
+    }
+
+    async fn update(&mut self, chunk: &[u8]) -> Result<Vec<u8>, Error> {
+        let detected;
+        let chunk = if let Self::Detecting(pending) = self {
+            pending.extend_from_slice(chunk);
+            match self.detect(false)? {
+                Some(pending) => {
+                    detected = pending;
+                    detected.as_slice()
+                }
+                None => return Ok(Vec::new()),
+            }
+        } else {
+            chunk
+        };
+        match self {
+            Self::Brotli(d) => {
+                d.write_all(chunk).await?;
+                Ok(mem::take(d.get_mut()))
+            }
+            Self::GZip(d) => {
+                d.write_all(chunk).await?;
+                Ok(mem::take(d.get_mut()))
+            }
+            Self::ZLib(d) => {
+                d.write_all(chunk).await?;
+                Ok(mem::take(d.get_mut()))
+            }
+            Self::Zstd(d) => {
+                d.write_all(chunk).await?;
+                Ok(mem::take(d.get_mut()))
+            }
+            Self::LZ4(d) => d.update(chunk),
+            Self::Detecting(_) => unreachable!(),
+        }
+    }
+
+    async fn finish(mut self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        if let Some(pending) = self.detect(true)? {
+            bytes = self.update(&pending).await?;
+        }
+        let rest = match self {
+            Self::Brotli(mut d) => {
+                d.close().await?;
+                d.into_inner()
+            }
+            Self::GZip(mut d) => {
+                d.close().await?;
+                d.into_inner()
+            }
+            Self::ZLib(mut d) => {
+                d.close().await?;
+                d.into_inner()
+            }
+            Self::Zstd(mut d) => {
+                d.close().await?;
+                d.into_inner()
+            }
+            Self::LZ4(d) => d.finish()?,
+            Self::Detecting(_) => unreachable!(),
+        };
+        bytes.extend(rest);
+        Ok(bytes)
+    }
+}
+
+// Outer (lua-accessible, clonable) implementations
+
+/**
+    An incremental compressor, which compresses chunks of bytes as they are given.
+*/
+#[derive(Clone)]
+pub struct Compressor {
+    inner: Rc<AsyncMutex<Option<CompressorInner>>>,
+}
+
+impl Compressor {
+    /**
+        Creates a new compressor for the given format and compression level.
+
+        # Errors
+
+        Errors when the compressor could not be created.
+    */
+    pub fn new(format: CompressDecompressFormat, level: Option<i32>) -> LuaResult<Self> {
+        let inner = CompressorInner::new(format, level)?;
+        Ok(Self {
+            inner: Rc::new(AsyncMutex::new(Some(inner))),
+        })
+    }
+}
+
+impl LuaUserData for Compressor {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_async_method("update", |lua, this, chunk: BString| {
+            let inner = this.inner.clone();
+            async move {
+                let mut inner = inner.lock().await;
+                let Some(compressor) = inner.as_mut() else {
+                    return Err(finished_error("Compressor"));
+                };
+                let bytes = compressor.update(&chunk).await?;
+                lua.create_string(bytes)
+            }
+        });
+        methods.add_async_method("finish", |lua, this, (): ()| {
+            let inner = this.inner.clone();
+            async move {
+                let mut inner = inner.lock().await;
+                let Some(compressor) = inner.take() else {
+                    return Err(finished_error("Compressor"));
+                };
+                let bytes = compressor.finish().await?;
+                lua.create_string(bytes)
+            }
+        });
+    }
+}
+
+/**
+    An incremental decompressor, which decompresses chunks of bytes as they are given.
+*/
+#[derive(Clone)]
+pub struct Decompressor {
+    inner: Rc<AsyncMutex<Option<DecompressorInner>>>,
+}
+
+impl Decompressor {
+    /**
+        Creates a new decompressor for the given format.
+
+        If no format is given, it will be detected from the first bytes given to the decompressor.
+
+        # Errors
+
+        Errors when the decompressor could not be created.
+    */
+    pub fn new(format: Option<CompressDecompressFormat>) -> LuaResult<Self> {
+        let inner = DecompressorInner::new(format)?;
+        Ok(Self {
+            inner: Rc::new(AsyncMutex::new(Some(inner))),
+        })
+    }
+}
+
+impl LuaUserData for Decompressor {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_async_method("update", |lua, this, chunk: BString| {
+            let inner = this.inner.clone();
+            async move {
+                let mut inner = inner.lock().await;
+                let Some(decompressor) = inner.as_mut() else {
+                    return Err(finished_error("Decompressor"));
+                };
+                let bytes = decompressor.update(&chunk).await?;
+                lua.create_string(bytes)
+            }
+        });
+        methods.add_async_method("finish", |lua, this, (): ()| {
+            let inner = this.inner.clone();
+            async move {
+                let mut inner = inner.lock().await;
+                let Some(decompressor) = inner.take() else {
+                    return Err(finished_error("Decompressor"));
+                };
+                let bytes = decompressor.finish().await?;
+                lua.create_string(bytes)
+            }
+        });
+    }
+}
+
+fn finished_error(kind: &str) -> LuaError {
+    LuaError::RuntimeError(format!("{kind} has already been finished"))
+}
@@ -0,0 +1,87 @@
+use ciborium::Value as CborValue;
+use rmpv::Value as MsgPackValue;
+use serde_json::Value as JsonValue;
+use toml::Value as TomlValue;
+
+/*
+    Lua numbers are 64-bit floats, which can only represent integers exactly
+    up to 2^53 - 1 - anything larger than that would silently lose precision,
+    so these functions replace such integers with their string representation.
+*/
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+fn is_unsafe_i128(i: i128) -> bool {
+    i.unsigned_abs() > u128::from(MAX_SAFE_INTEGER)
+}
+
+pub fn json_to_strings(value: &mut JsonValue) {
+    match value {
+        JsonValue::Number(n) => {
+            let unsafe_int = match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) => is_unsafe_i128(i128::from(i)),
+                (None, Some(u)) => is_unsafe_i128(i128::from(u)),
+                (None, None) => false,
+            };
+            if unsafe_int {
+                *value = JsonValue::String(n.to_string());
+            }
+        }
+        JsonValue::Array(values) => values.iter_mut().for_each(json_to_strings),
+        JsonValue::Object(map) => map.values_mut().for_each(json_to_strings),
+        _ => {}
+    }
+}
+
+pub fn toml_to_strings(value: &mut TomlValue) {
+    match value {
+        TomlValue::Integer(i) if is_unsafe_i128(i128::from(*i)) => {
+            *value = TomlValue::String(i.to_string());
+        }
+        TomlValue::Array(values) => values.iter_mut().for_each(toml_to_strings),
+        TomlValue::Table(map) => map.iter_mut().for_each(|(_, value)| toml_to_strings(value)),
+        _ => {}
+    }
+}
+
+pub fn msgpack_to_strings(value: &mut MsgPackValue) {
+    match value {
+        MsgPackValue::Integer(i) => {
+            let unsafe_int = match (i.as_i64(), i.as_u64()) {
+                (Some(i), _) => is_unsafe_i128(i128::from(i)),
+                (None, Some(u)) => is_unsafe_i128(i128::from(u)),
+                (None, None) => false,
+            };
+            if unsafe_int {
+                *value = MsgPackValue::String(i.to_string().into());
+            }
+        }
+        MsgPackValue::Array(values) => values.iter_mut().for_each(msgpack_to_strings),
+        MsgPackValue::Map(entries) => {
+            for (key, value) in entries {
+                msgpack_to_strings(key);
+                msgpack_to_strings(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn cbor_to_strings(value: &mut CborValue) {
+    match value {
+        CborValue::Integer(i) => {
+            let i = i128::from(*i);
+            if is_unsafe_i128(i) {
+                *value = CborValue::Text(i.to_string());
+            }
+        }
+        CborValue::Tag(_, inner) => cbor_to_strings(inner),
+        CborValue::Array(values) => values.iter_mut().for_each(cbor_to_strings),
+        CborValue::Map(entries) => {
+            for (key, value) in entries {
+                cbor_to_strings(key);
+                cbor_to_strings(value);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,343 @@
+use std::fmt;
+
+use bstr::BString;
+use csv::{ByteRecord, ReaderBuilder, WriterBuilder};
+use mlua::prelude::*;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{MapAccess, SeqAccess, Visitor},
+    ser::SerializeMap,
+};
+
+/**
+    How the header row of a CSV document should be handled.
+*/
+#[derive(Debug, Clone, Default)]
+pub enum CsvHeader {
+    /// There is no header row, rows are arrays of fields.
+    None,
+    /// The first row is the header row, other rows are keyed by its fields.
+    #[default]
+    FirstRow,
+    /// There is no header row, rows are keyed by the given column names.
+    Columns(Vec<BString>),
+}
+
+impl FromLua for CsvHeader {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::Boolean(true) => Ok(Self::FirstRow),
+            LuaValue::Boolean(false) => Ok(Self::None),
+            LuaValue::Table(_) => Ok(Self::Columns(Vec::<BString>::from_lua(value, lua)?)),
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "CsvHeader".to_string(),
+                message: Some(format!(
+                    "Invalid csv header - expected boolean or table of column names, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
+
+/**
+    Options specific to encoding and decoding CSV documents.
+*/
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub header: CsvHeader,
+    pub delimiter: u8,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            header: CsvHeader::default(),
+            delimiter: b',',
+        }
+    }
+}
+
+impl CsvOptions {
+    /**
+        Parses a delimiter from a lua string, which must be exactly one byte long.
+    */
+    pub(super) fn parse_delimiter(delimiter: &LuaString) -> LuaResult<u8> {
+        match delimiter.as_bytes().as_ref() {
+            [byte] => Ok(*byte),
+            _ => Err(LuaError::RuntimeError(format!(
+                "Invalid csv delimiter '{}' - must be a single character",
+                delimiter.to_string_lossy()
+            ))),
+        }
+    }
+}
+
+/**
+    A single field in a CSV document.
+
+    Fields are always raw bytes, which means that any non-utf8 strings are preserved as-is.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CsvField(Vec<u8>);
+
+impl Serialize for CsvField {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for CsvField {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(CsvFieldVisitor)
+    }
+}
+
+struct CsvFieldVisitor;
+
+impl Visitor<'_> for CsvFieldVisitor {
+    type Value = CsvField;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string, number, boolean, or nil")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(CsvField(v.to_string().into_bytes()))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(CsvField(v.to_string().into_bytes()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(CsvField(v.to_string().into_bytes()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(CsvField(v.to_string().into_bytes()))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(CsvField(v.as_bytes().to_vec()))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(CsvField(v.to_vec()))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(CsvField::default())
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(CsvField::default())
+    }
+}
+
+/**
+    A single row in a CSV document, either as an array of fields
+    or as a map of column names to fields, depending on the header.
+*/
+#[derive(Debug, Clone)]
+enum CsvRow {
+    List(Vec<CsvField>),
+    Map(Vec<(CsvField, CsvField)>),
+}
+
+impl Serialize for CsvRow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::List(fields) => fields.serialize(serializer),
+            Self::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CsvRow {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(CsvRowVisitor)
+    }
+}
+
+struct CsvRowVisitor;
+
+impl<'de> Visitor<'de> for CsvRowVisitor {
+    type Value = CsvRow;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of fields or a table of column names to fields")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut fields = Vec::new();
+        while let Some(field) = seq.next_element()? {
+            fields.push(field);
+        }
+        Ok(CsvRow::List(fields))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(CsvRow::Map(entries))
+    }
+}
+
+/**
+    Converts the given lua value into a CSV document.
+
+    The value must be an array of rows, where each row is either an array of fields,
+    or a table of column names to fields, if the options contain a header.
+*/
+pub fn encode(
+    value: LuaValue,
+    lua: &Lua,
+    options: &CsvOptions,
+    lua_options: LuaDeserializeOptions,
+) -> LuaResult<Vec<u8>> {
+    let rows: Vec<CsvRow> = lua.from_value_with(value, lua_options)?;
+
+    let columns: Option<Vec<CsvField>> = match &options.header {
+        CsvHeader::None => None,
+        CsvHeader::Columns(columns) => Some(
+            columns
+                .iter()
+                .map(|column| CsvField(column.to_vec()))
+                .collect(),
+        ),
+        // Column names are gathered from all of the rows, in the order they are first seen
+        CsvHeader::FirstRow => {
+            let mut columns = Vec::new();
+            for row in &rows {
+                if let CsvRow::Map(entries) = row {
+                    for (key, _) in entries {
+                        if !columns.contains(key) {
+                            columns.push(key.clone());
+                        }
+                    }
+                }
+            }
+            (!columns.is_empty()).then_some(columns)
+        }
+    };
+
+    let mut writer = WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_writer(Vec::new());
+
+    if let Some(columns) = &columns {
+        writer
+            .write_record(columns.iter().map(|column| &column.0))
+            .into_lua_err()?;
+    }
+
+    for (index, row) in rows.into_iter().enumerate() {
+        match row {
+            CsvRow::List(fields) => writer
+                .write_record(fields.iter().map(|field| &field.0))
+                .into_lua_err()?,
+            CsvRow::Map(mut entries) => {
+                let Some(columns) = &columns else {
+                    return Err(LuaError::RuntimeError(format!(
+                        "Row {} must be an array of fields when encoding csv without a header",
+                        index + 1
+                    )));
+                };
+                let record = columns.iter().map(|column| {
+                    entries
+                        .iter()
+                        .position(|(key, _)| key == column)
+                        .map(|pos| entries.swap_remove(pos).1)
+                        .unwrap_or_default()
+                        .0
+                });
+                let record: ByteRecord = record.collect();
+                if let Some((key, _)) = entries.first() {
+                    return Err(LuaError::RuntimeError(format!(
+                        "Row {} contains the field '{}', which is not in the csv header",
+                        index + 1,
+                        String::from_utf8_lossy(&key.0)
+                    )));
+                }
+                writer.write_byte_record(&record).into_lua_err()?;
+            }
+        }
+    }
+
+    writer.into_inner().into_lua_err()
+}
+
+/**
+    Converts the given CSV document into a lua value.
+
+    The returned value is an array of rows, where each row is either an array
+    of fields, or a table of column names to fields, if the options contain a header.
+*/
+pub fn decode(
+    bytes: &[u8],
+    lua: &Lua,
+    options: &CsvOptions,
+    lua_options: LuaSerializeOptions,
+) -> LuaResult<LuaValue> {
+    let mut reader = ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .has_headers(false)
+        .flexible(matches!(options.header, CsvHeader::None))
+        .from_reader(bytes);
+
+    let mut records = reader.byte_records();
+
+    let columns: Option<Vec<CsvField>> = match &options.header {
+        CsvHeader::None => None,
+        CsvHeader::Columns(columns) => Some(
+            columns
+                .iter()
+                .map(|column| CsvField(column.to_vec()))
+                .collect(),
+        ),
+        CsvHeader::FirstRow => match records.next() {
+            Some(header) => Some(
+                header
+                    .into_lua_err()?
+                    .iter()
+                    .map(|column| CsvField(column.to_vec()))
+                    .collect(),
+            ),
+            None => Some(Vec::new()),
+        },
+    };
+
+    let mut rows = Vec::new();
+    for record in records {
+        let record = record.into_lua_err()?;
+        let fields = record.iter().map(|field| CsvField(field.to_vec()));
+        let row = match &columns {
+            None => CsvRow::List(fields.collect()),
+            Some(columns) => {
+                if record.len() != columns.len() {
+                    return Err(LuaError::RuntimeError(format!(
+                        "Found a csv row with {} fields, but the header has {} columns",
+                        record.len(),
+                        columns.len()
+                    )));
+                }
+                CsvRow::Map(columns.iter().cloned().zip(fields).collect())
+            }
+        };
+        rows.push(row);
+    }
+
+    lua.to_value_with(&rows, lua_options)
+}
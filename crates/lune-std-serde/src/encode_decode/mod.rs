@@ -0,0 +1,288 @@
+use mlua::prelude::*;
+use serde::{Serialize, de::DeserializeOwned};
+
+use ciborium::Value as CborValue;
+use rmpv::Value as MsgPackValue;
+use serde_json::Value as JsonValue;
+use serde_json::ser::PrettyFormatter;
+use serde_yaml2::wrapper::YamlNodeWrapper as YamlValue;
+use toml::Value as TomlValue;
+
+mod big_ints;
+mod csv;
+mod options;
+mod ordered;
+
+pub use self::csv::{CsvHeader, CsvOptions};
+pub use self::options::{EmptyTables, EncodeDecodeOptions};
+pub use self::ordered::OrderedTables;
+
+use self::ordered::{OrderedValue, apply_key_order, record_key_order};
+
+/**
+    An encoding and decoding format supported by Lune.
+
+    Encode / decode in this case is synonymous with serialize / deserialize.
+*/
+#[derive(Debug, Clone, Copy)]
+pub enum EncodeDecodeFormat {
+    Json,
+    JsonC,
+    Yaml,
+    Toml,
+    MsgPack,
+    Cbor,
+    Csv,
+}
+
+impl FromLua for EncodeDecodeFormat {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        if let LuaValue::String(s) = &value {
+            match s.to_string_lossy().to_ascii_lowercase().trim() {
+                "json" => Ok(Self::Json),
+                "jsonc" => Ok(Self::JsonC),
+                "yaml" => Ok(Self::Yaml),
+                "toml" => Ok(Self::Toml),
+                "msgpack" => Ok(Self::MsgPack),
+                "cbor" => Ok(Self::Cbor),
+                "csv" => Ok(Self::Csv),
+                kind => Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "EncodeDecodeFormat".to_string(),
+                    message: Some(format!(
+                        "Invalid format '{kind}', valid formats are: json, jsonc, yaml, toml, msgpack, cbor, csv"
+                    )),
+                }),
+            }
+        } else {
+            Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "EncodeDecodeFormat".to_string(),
+                message: None,
+            })
+        }
+    }
+}
+
+/**
+    Configuration for encoding and decoding values.
+
+    Encoding / decoding in this case is synonymous with serialize / deserialize.
+*/
+#[derive(Debug, Clone)]
+pub struct EncodeDecodeConfig {
+    pub format: EncodeDecodeFormat,
+    pub options: EncodeDecodeOptions,
+}
+
+impl From<EncodeDecodeFormat> for EncodeDecodeConfig {
+    fn from(format: EncodeDecodeFormat) -> Self {
+        Self {
+            format,
+            options: EncodeDecodeOptions::default(),
+        }
+    }
+}
+
+impl From<(EncodeDecodeFormat, bool)> for EncodeDecodeConfig {
+    fn from(value: (EncodeDecodeFormat, bool)) -> Self {
+        Self {
+            format: value.0,
+            options: EncodeDecodeOptions::from(value.1),
+        }
+    }
+}
+
+impl From<(EncodeDecodeFormat, EncodeDecodeOptions)> for EncodeDecodeConfig {
+    fn from(value: (EncodeDecodeFormat, EncodeDecodeOptions)) -> Self {
+        Self {
+            format: value.0,
+            options: value.1,
+        }
+    }
+}
+
+/**
+    Encodes / serializes the given value into a string, using the specified configuration.
+
+    # Errors
+
+    Errors when the encoding fails.
+*/
+pub fn encode(value: LuaValue, lua: &Lua, config: &EncodeDecodeConfig) -> LuaResult<LuaString> {
+    let options = &config.options;
+    let lua_options = options.lua_deserialize_options();
+    let bytes = match config.format {
+        EncodeDecodeFormat::Json | EncodeDecodeFormat::JsonC => {
+            let serialized: JsonValue = from_lua_ordered(lua, value, lua_options)?;
+            if let Some(indent) = options.pretty_indent() {
+                let mut bytes = Vec::new();
+                let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+                let mut serializer = serde_json::Serializer::with_formatter(&mut bytes, formatter);
+                serialized.serialize(&mut serializer).into_lua_err()?;
+                bytes
+            } else {
+                serde_json::to_vec(&serialized).into_lua_err()?
+            }
+        }
+        EncodeDecodeFormat::Yaml => {
+            let serialized: YamlValue = lua.from_value_with(value.clone(), lua_options)?;
+            let mut serialized = serialized.get();
+            if let Some(tables) = OrderedTables::get(lua)? {
+                apply_key_order(&tables, &value, &mut serialized)?;
+            }
+            serde_yaml2::to_string(YamlValue::new(serialized))
+                .into_lua_err()?
+                .into_bytes()
+        }
+        EncodeDecodeFormat::Toml => {
+            let serialized: TomlValue = from_lua_ordered(lua, value, lua_options)?;
+            let s = if options.pretty_indent().is_some() {
+                toml::to_string_pretty(&serialized).into_lua_err()?
+            } else {
+                toml::to_string(&serialized).into_lua_err()?
+            };
+            s.as_bytes().to_vec()
+        }
+        EncodeDecodeFormat::MsgPack => {
+            let serialized: MsgPackValue = from_lua_ordered(lua, value, lua_options)?;
+            let mut bytes = Vec::new();
+            rmpv::encode::write_value(&mut bytes, &serialized).into_lua_err()?;
+            bytes
+        }
+        EncodeDecodeFormat::Cbor => {
+            let serialized: CborValue = from_lua_ordered(lua, value, lua_options)?;
+            let mut bytes = Vec::new();
+            ciborium::into_writer(&serialized, &mut bytes).into_lua_err()?;
+            bytes
+        }
+        EncodeDecodeFormat::Csv => csv::encode(value, lua, &options.csv, lua_options)?,
+    };
+    lua.create_string(bytes)
+}
+
+/**
+    Decodes / deserializes the given string into a value, using the specified configuration.
+
+    # Errors
+
+    Errors when the decoding fails.
+*/
+pub fn decode(
+    bytes: impl AsRef<[u8]>,
+    lua: &Lua,
+    config: &EncodeDecodeConfig,
+) -> LuaResult<LuaValue> {
+    let bytes = bytes.as_ref();
+    let options = &config.options;
+    let lua_options = options.lua_serialize_options();
+    let order = options.preserve_order;
+    match config.format {
+        EncodeDecodeFormat::Json => {
+            let mut value: JsonValue = serde_json::from_slice(bytes).into_lua_err()?;
+            if options.big_ints_as_strings {
+                big_ints::json_to_strings(&mut value);
+            }
+            to_lua_ordered(lua, &mut value, lua_options, order)
+        }
+        EncodeDecodeFormat::JsonC => {
+            let string: String = String::from_utf8(bytes.to_vec()).into_lua_err()?;
+            let mut value: JsonValue =
+                jsonc_parser::parse_to_serde_value(&string, &jsonc_parser::ParseOptions::default())
+                    .map(|v| v.unwrap_or(JsonValue::Null))
+                    .into_lua_err()?;
+            if options.big_ints_as_strings {
+                big_ints::json_to_strings(&mut value);
+            }
+            to_lua_ordered(lua, &mut value, lua_options, order)
+        }
+        EncodeDecodeFormat::Yaml => {
+            let string: String = String::from_utf8(bytes.to_vec()).into_lua_err()?;
+            let value: YamlValue = serde_yaml2::from_str(&string).into_lua_err()?;
+            let lua_value = lua.to_value_with(&value, lua_options)?;
+            if order {
+                let tables = OrderedTables::get_or_init(lua)?;
+                record_key_order(lua, &tables, &lua_value, &mut value.get())?;
+            }
+            Ok(lua_value)
+        }
+        EncodeDecodeFormat::Toml => {
+            if let Ok(s) = String::from_utf8(bytes.to_vec()) {
+                let mut value: TomlValue = toml::from_str(&s).into_lua_err()?;
+                if options.big_ints_as_strings {
+                    big_ints::toml_to_strings(&mut value);
+                }
+                to_lua_ordered(lua, &mut value, lua_options, order)
+            } else {
+                Err(LuaError::RuntimeError(
+                    "TOML must be valid utf-8".to_string(),
+                ))
+            }
+        }
+        EncodeDecodeFormat::MsgPack => {
+            let mut reader = bytes;
+            let mut value: MsgPackValue = rmpv::decode::read_value(&mut reader).into_lua_err()?;
+            if !reader.is_empty() {
+                return Err(LuaError::RuntimeError(format!(
+                    "MessagePack contained {} unexpected trailing bytes",
+                    reader.len()
+                )));
+            }
+            if options.big_ints_as_strings {
+                big_ints::msgpack_to_strings(&mut value);
+            }
+            to_lua_ordered(lua, &mut value, lua_options, order)
+        }
+        EncodeDecodeFormat::Cbor => {
+            let mut reader = bytes;
+            let mut value: CborValue = ciborium::from_reader(&mut reader).into_lua_err()?;
+            if !reader.is_empty() {
+                return Err(LuaError::RuntimeError(format!(
+                    "CBOR contained {} unexpected trailing bytes",
+                    reader.len()
+                )));
+            }
+            if options.big_ints_as_strings {
+                big_ints::cbor_to_strings(&mut value);
+            }
+            to_lua_ordered(lua, &mut value, lua_options, order)
+        }
+        EncodeDecodeFormat::Csv => csv::decode(bytes, lua, &options.csv, lua_options),
+    }
+}
+
+/**
+    Converts the given lua value into a value to encode, keeping
+    the order of keys for any ordered tables within it.
+*/
+fn from_lua_ordered<T>(lua: &Lua, value: LuaValue, options: LuaDeserializeOptions) -> LuaResult<T>
+where
+    T: DeserializeOwned + OrderedValue,
+{
+    let mut serialized: T = lua.from_value_with(value.clone(), options)?;
+    if let Some(tables) = OrderedTables::get(lua)? {
+        apply_key_order(&tables, &value, &mut serialized)?;
+    }
+    Ok(serialized)
+}
+
+/**
+    Converts the given decoded value into a lua value, marking
+    any tables within it as ordered, if the order should be preserved.
+*/
+fn to_lua_ordered<T>(
+    lua: &Lua,
+    value: &mut T,
+    options: LuaSerializeOptions,
+    preserve_order: bool,
+) -> LuaResult<LuaValue>
+where
+    T: Serialize + OrderedValue,
+{
+    let lua_value = lua.to_value_with(&*value, options)?;
+    if preserve_order {
+        let tables = OrderedTables::get_or_init(lua)?;
+        record_key_order(lua, &tables, &lua_value, value)?;
+    }
+    Ok(lua_value)
+}
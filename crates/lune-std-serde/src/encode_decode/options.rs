@@ -0,0 +1,194 @@
+use mlua::prelude::*;
+
+use super::csv::{CsvHeader, CsvOptions};
+
+// NOTE: These are options for going from other format -> lua ("serializing" lua values)
+const LUA_SERIALIZE_OPTIONS: LuaSerializeOptions = LuaSerializeOptions::new()
+    .set_array_metatable(false)
+    .serialize_none_to_null(false)
+    .serialize_unit_to_null(false);
+
+// NOTE: These are options for going from lua -> other format ("deserializing" lua values)
+const LUA_DESERIALIZE_OPTIONS: LuaDeserializeOptions = LuaDeserializeOptions::new()
+    .sort_keys(true)
+    .deny_recursive_tables(false)
+    .deny_unsupported_types(true);
+
+const DEFAULT_INDENT: &str = "  ";
+
+/**
+    How empty tables, which could be either arrays or objects, are encoded.
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyTables {
+    #[default]
+    Object,
+    Array,
+}
+
+impl FromLua for EmptyTables {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        if let LuaValue::String(s) = &value {
+            match s.to_string_lossy().to_ascii_lowercase().trim() {
+                "object" => Ok(Self::Object),
+                "array" => Ok(Self::Array),
+                kind => Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "EmptyTables".to_string(),
+                    message: Some(format!(
+                        "Invalid empty tables kind '{kind}', valid kinds are: object, array"
+                    )),
+                }),
+            }
+        } else {
+            Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "EmptyTables".to_string(),
+                message: None,
+            })
+        }
+    }
+}
+
+/**
+    Options for encoding and decoding values, as given from lua.
+
+    This may be either a boolean, for only setting `pretty`, or a table of options.
+*/
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct EncodeDecodeOptions {
+    pub pretty: bool,
+    pub indent: Option<String>,
+    pub sort_keys: bool,
+    pub preserve_order: bool,
+    pub use_null: bool,
+    pub empty_tables: EmptyTables,
+    pub array_hints: bool,
+    pub big_ints_as_strings: bool,
+    pub csv: CsvOptions,
+}
+
+impl EncodeDecodeOptions {
+    /**
+        Returns the indentation to use when encoding, if the output should be pretty.
+    */
+    #[must_use]
+    pub fn pretty_indent(&self) -> Option<&str> {
+        match &self.indent {
+            Some(indent) => Some(indent),
+            None if self.pretty => Some(DEFAULT_INDENT),
+            None => None,
+        }
+    }
+
+    /**
+        Returns options for converting decoded values into lua values.
+    */
+    #[must_use]
+    pub fn lua_serialize_options(&self) -> LuaSerializeOptions {
+        LUA_SERIALIZE_OPTIONS
+            .set_array_metatable(self.array_hints)
+            .serialize_none_to_null(self.use_null)
+            .serialize_unit_to_null(self.use_null)
+    }
+
+    /**
+        Returns options for converting lua values into values to encode.
+    */
+    #[must_use]
+    pub fn lua_deserialize_options(&self) -> LuaDeserializeOptions {
+        LUA_DESERIALIZE_OPTIONS
+            .sort_keys(self.sort_keys)
+            .encode_empty_tables_as_array(self.empty_tables == EmptyTables::Array)
+    }
+}
+
+impl Default for EncodeDecodeOptions {
+    fn default() -> Self {
+        Self {
+            pretty: false,
+            indent: None,
+            sort_keys: true,
+            preserve_order: false,
+            use_null: false,
+            empty_tables: EmptyTables::default(),
+            array_hints: false,
+            big_ints_as_strings: false,
+            csv: CsvOptions::default(),
+        }
+    }
+}
+
+impl From<bool> for EncodeDecodeOptions {
+    fn from(pretty: bool) -> Self {
+        Self {
+            pretty,
+            ..Self::default()
+        }
+    }
+}
+
+impl FromLua for EncodeDecodeOptions {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::Boolean(pretty) => Ok(Self::from(pretty)),
+            LuaValue::Table(t) => {
+                let defaults = Self::default();
+
+                let indent = match t.get::<LuaValue>("indent")? {
+                    LuaValue::Nil => None,
+                    value @ (LuaValue::Integer(_) | LuaValue::Number(_)) => {
+                        Some(" ".repeat(usize::from_lua(value, lua)?))
+                    }
+                    LuaValue::String(s) => Some(s.to_str()?.to_string()),
+                    value => {
+                        return Err(LuaError::RuntimeError(format!(
+                            "Invalid indent - expected a positive integer or string, got {}",
+                            value.type_name()
+                        )));
+                    }
+                };
+
+                let header = CsvHeader::from_lua(t.get("header")?, lua)?;
+                let delimiter = match t.get::<Option<LuaString>>("delimiter")? {
+                    Some(delimiter) => CsvOptions::parse_delimiter(&delimiter)?,
+                    None => defaults.csv.delimiter,
+                };
+
+                Ok(Self {
+                    pretty: t.get::<Option<bool>>("pretty")?.unwrap_or(defaults.pretty),
+                    indent,
+                    sort_keys: t
+                        .get::<Option<bool>>("sortKeys")?
+                        .unwrap_or(defaults.sort_keys),
+                    preserve_order: t
+                        .get::<Option<bool>>("preserveOrder")?
+                        .unwrap_or(defaults.preserve_order),
+                    use_null: t
+                        .get::<Option<bool>>("useNull")?
+                        .unwrap_or(defaults.use_null),
+                    empty_tables: t
+                        .get::<Option<EmptyTables>>("emptyTables")?
+                        .unwrap_or(defaults.empty_tables),
+                    array_hints: t
+                        .get::<Option<bool>>("arrayHints")?
+                        .unwrap_or(defaults.array_hints),
+                    big_ints_as_strings: t
+                        .get::<Option<bool>>("bigIntsAsStrings")?
+                        .unwrap_or(defaults.big_ints_as_strings),
+                    csv: CsvOptions { header, delimiter },
+                })
+            }
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "EncodeDecodeOptions".to_string(),
+                message: Some(format!(
+                    "Invalid options - expected boolean or table, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+use ciborium::Value as CborValue;
+use mlua::prelude::*;
+use rmpv::Value as MsgPackValue;
+use serde_json::Value as JsonValue;
+use toml::Value as TomlValue;
+use yaml_rust2::Yaml;
+
+const REGISTRY_KEY: &str = "__SERDE_ORDERED_TABLES";
+
+/*
+    Lua tables do not keep track of the order that their keys were inserted in,
+    so ordered tables share a metatable with a `__newindex` metamethod, which
+    records new keys in a separate table, using weak keys for the ordered tables.
+
+    Encoding first converts lua values as usual, and then walks the encoded value
+    and the lua value at the same time, moving keys of ordered tables into place.
+*/
+
+/**
+    Tables created using `serde.ordered`, or decoded using the
+    `preserveOrder` option, and the order of their keys.
+*/
+#[derive(Debug, Clone)]
+pub struct OrderedTables {
+    metatable: LuaTable,
+    keys: LuaTable,
+}
+
+impl OrderedTables {
+    /**
+        Gets the ordered tables for the given Lua state, if any ordered table was ever created.
+
+        # Errors
+
+        Errors when the ordered tables stored in the Lua registry are invalid.
+    */
+    pub fn get(lua: &Lua) -> LuaResult<Option<Self>> {
+        let Some(registry) = lua.named_registry_value::<Option<LuaTable>>(REGISTRY_KEY)? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            metatable: registry.raw_get("metatable")?,
+            keys: registry.raw_get("keys")?,
+        }))
+    }
+
+    /**
+        Gets the ordered tables for the given Lua state, initializing them if necessary.
+
+        # Errors
+
+        Errors when out of memory.
+    */
+    pub fn get_or_init(lua: &Lua) -> LuaResult<Self> {
+        if let Some(this) = Self::get(lua)? {
+            return Ok(this);
+        }
+
+        let keys = lua.create_table()?;
+        keys.set_metatable(Some(lua.create_table_from([("__mode", "k")])?))?;
+
+        let metatable = lua.create_table()?;
+        metatable.raw_set(
+            "__newindex",
+            lua.create_function({
+                let keys = keys.clone();
+                move |lua, (table, key, value): (LuaTable, LuaValue, LuaValue)| {
+                    table.raw_set(key.clone(), value.clone())?;
+                    if !value.is_nil() {
+                        if let Some(order) = keys.raw_get::<Option<LuaTable>>(&table)? {
+                            order.raw_push(key)?;
+                        } else {
+                            keys.raw_set(&table, lua.create_sequence_from([key])?)?;
+                        }
+                    }
+                    Ok(())
+                }
+            })?,
+        )?;
+
+        let registry = lua.create_table()?;
+        registry.raw_set("metatable", &metatable)?;
+        registry.raw_set("keys", &keys)?;
+        lua.set_named_registry_value(REGISTRY_KEY, registry)?;
+
+        Ok(Self { metatable, keys })
+    }
+
+    /**
+        Creates a new, empty ordered table.
+
+        # Errors
+
+        Errors when out of memory.
+    */
+    pub fn create_table(&self, lua: &Lua) -> LuaResult<LuaTable> {
+        let table = lua.create_table()?;
+        table.set_metatable(Some(self.metatable.clone()))?;
+        Ok(table)
+    }
+
+    fn mark(&self, lua: &Lua, table: &LuaTable, keys: Vec<&str>) -> LuaResult<()> {
+        table.set_metatable(Some(self.metatable.clone()))?;
+        self.keys.raw_set(table, lua.create_sequence_from(keys)?)
+    }
+
+    /**
+        Returns the position of each string key in the given table, if it is an ordered table.
+
+        Keys that were removed and then inserted again are positioned by their last insertion.
+    */
+    fn positions(&self, table: &LuaTable) -> LuaResult<Option<HashMap<String, usize>>> {
+        let Some(order) = self.keys.raw_get::<Option<LuaTable>>(table)? else {
+            return Ok(None);
+        };
+        let mut positions = HashMap::new();
+        for (index, key) in order.sequence_values::<LuaValue>().enumerate() {
+            if let LuaValue::String(key) = key? {
+                positions.insert(key.to_str()?.to_string(), index);
+            }
+        }
+        Ok(Some(positions))
+    }
+}
+
+/**
+    A child of an encoded value, keyed the same way as in the Lua table it came from.
+*/
+pub enum Child<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/**
+    An encoded value, which may be a map that keeps the order of its keys.
+*/
+pub trait OrderedValue: Sized {
+    /**
+        Returns the string keys of this value, in order, if it is a map.
+    */
+    fn map_keys(&self) -> Option<Vec<&str>>;
+
+    /**
+        Sorts the entries of this value by the position of their keys, if it is a map.
+
+        Positions are given as `None` for keys that are not strings.
+    */
+    fn sort_map(&mut self, position: impl FnMut(Option<&str>) -> usize);
+
+    /**
+        Returns the children of this value, if it is a map or an array.
+    */
+    fn children_mut(&mut self) -> Vec<(Child<'_>, &mut Self)>;
+}
+
+/**
+    Marks all tables in the given decoded Lua value as ordered,
+    using the order of keys in the value it was decoded from.
+*/
+pub fn record_key_order<T: OrderedValue>(
+    lua: &Lua,
+    tables: &OrderedTables,
+    lua_value: &LuaValue,
+    value: &mut T,
+) -> LuaResult<()> {
+    let LuaValue::Table(table) = lua_value else {
+        return Ok(());
+    };
+    if let Some(keys) = value.map_keys() {
+        tables.mark(lua, table, keys)?;
+    }
+    for (child, value) in value.children_mut() {
+        record_key_order(lua, tables, &get_child(table, &child)?, value)?;
+    }
+    Ok(())
+}
+
+/**
+    Moves the keys of all maps in the given encoded value into the order that they
+    were inserted in, for maps that were encoded from ordered tables in the Lua value.
+
+    Any keys that are not known to the ordered table are kept after all known keys.
+*/
+pub fn apply_key_order<T: OrderedValue>(
+    tables: &OrderedTables,
+    lua_value: &LuaValue,
+    value: &mut T,
+) -> LuaResult<()> {
+    let LuaValue::Table(table) = lua_value else {
+        return Ok(());
+    };
+    if let Some(positions) = tables.positions(table)? {
+        value.sort_map(|key| {
+            key.and_then(|key| positions.get(key).copied())
+                .unwrap_or(usize::MAX)
+        });
+    }
+    for (child, value) in value.children_mut() {
+        apply_key_order(tables, &get_child(table, &child)?, value)?;
+    }
+    Ok(())
+}
+
+fn get_child(table: &LuaTable, child: &Child) -> LuaResult<LuaValue> {
+    match child {
+        Child::Key(key) => table.raw_get(*key),
+        Child::Index(index) => table.raw_get(index + 1),
+    }
+}
+
+fn sort_entries<K, V>(
+    entries: impl IntoIterator<Item = (K, V)>,
+    mut position: impl FnMut(&K) -> usize,
+) -> Vec<(K, V)> {
+    let mut entries = entries.into_iter().collect::<Vec<_>>();
+    entries.sort_by_cached_key(|(key, _)| position(key));
+    entries
+}
+
+impl OrderedValue for JsonValue {
+    fn map_keys(&self) -> Option<Vec<&str>> {
+        match self {
+            Self::Object(map) => Some(map.keys().map(String::as_str).collect()),
+            _ => None,
+        }
+    }
+
+    fn sort_map(&mut self, mut position: impl FnMut(Option<&str>) -> usize) {
+        if let Self::Object(map) = self {
+            let entries = sort_entries(std::mem::take(map), |key| position(Some(key)));
+            *map = entries.into_iter().collect();
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<(Child<'_>, &mut Self)> {
+        match self {
+            Self::Object(map) => map
+                .iter_mut()
+                .map(|(key, value)| (Child::Key(key), value))
+                .collect(),
+            Self::Array(values) => values
+                .iter_mut()
+                .enumerate()
+                .map(|(index, value)| (Child::Index(index), value))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl OrderedValue for TomlValue {
+    fn map_keys(&self) -> Option<Vec<&str>> {
+        match self {
+            Self::Table(map) => Some(map.keys().map(String::as_str).collect()),
+            _ => None,
+        }
+    }
+
+    fn sort_map(&mut self, mut position: impl FnMut(Option<&str>) -> usize) {
+        if let Self::Table(map) = self {
+            let entries = sort_entries(std::mem::take(map), |key| position(Some(key)));
+            *map = entries.into_iter().collect();
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<(Child<'_>, &mut Self)> {
+        match self {
+            Self::Table(map) => map
+                .iter_mut()
+                .map(|(key, value)| (Child::Key(key), value))
+                .collect(),
+            Self::Array(values) => values
+                .iter_mut()
+                .enumerate()
+                .map(|(index, value)| (Child::Index(index), value))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl OrderedValue for Yaml {
+    fn map_keys(&self) -> Option<Vec<&str>> {
+        match self {
+            Self::Hash(map) => Some(map.keys().filter_map(Yaml::as_str).collect()),
+            _ => None,
+        }
+    }
+
+    fn sort_map(&mut self, mut position: impl FnMut(Option<&str>) -> usize) {
+        if let Self::Hash(map) = self {
+            let entries = sort_entries(std::mem::take(map), |key| position(key.as_str()));
+            *map = entries.into_iter().collect();
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<(Child<'_>, &mut Self)> {
+        match self {
+            Self::Hash(map) => map
+                .iter_mut()
+                .filter_map(|(key, value)| Some((Child::Key(key.as_str()?), value)))
+                .collect(),
+            Self::Array(values) => values
+                .iter_mut()
+                .enumerate()
+                .map(|(index, value)| (Child::Index(index), value))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl OrderedValue for MsgPackValue {
+    fn map_keys(&self) -> Option<Vec<&str>> {
+        match self {
+            Self::Map(entries) => {
+                Some(entries.iter().filter_map(|(key, _)| key.as_str()).collect())
+            }
+            _ => None,
+        }
+    }
+
+    fn sort_map(&mut self, mut position: impl FnMut(Option<&str>) -> usize) {
+        if let Self::Map(entries) = self {
+            *entries = sort_entries(std::mem::take(entries), |key| position(key.as_str()));
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<(Child<'_>, &mut Self)> {
+        match self {
+            Self::Map(entries) => entries
+                .iter_mut()
+                .filter_map(|(key, value)| Some((Child::Key(key.as_str()?), value)))
+                .collect(),
+            Self::Array(values) => values
+                .iter_mut()
+                .enumerate()
+                .map(|(index, value)| (Child::Index(index), value))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl OrderedValue for CborValue {
+    fn map_keys(&self) -> Option<Vec<&str>> {
+        match self {
+            Self::Map(entries) => Some(
+                entries
+                    .iter()
+                    .filter_map(|(key, _)| cbor_key(key))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    fn sort_map(&mut self, mut position: impl FnMut(Option<&str>) -> usize) {
+        if let Self::Map(entries) = self {
+            *entries = sort_entries(std::mem::take(entries), |key| position(cbor_key(key)));
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<(Child<'_>, &mut Self)> {
+        match self {
+            Self::Map(entries) => entries
+                .iter_mut()
+                .filter_map(|(key, value)| Some((Child::Key(cbor_key(key)?), value)))
+                .collect(),
+            Self::Array(values) => values
+                .iter_mut()
+                .enumerate()
+                .map(|(index, value)| (Child::Index(index), value))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn cbor_key(key: &CborValue) -> Option<&str> {
+    match key {
+        CborValue::Text(key) => Some(key),
+        _ => None,
+    }
+}
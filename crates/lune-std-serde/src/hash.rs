@@ -1,13 +1,17 @@
-use std::fmt::Write;
+use std::{cell::RefCell, fmt::Write, rc::Rc};
 
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use bstr::BString;
+use digest::DynDigest;
 use md5::Md5;
 use mlua::prelude::*;
 
+use blake2::{Blake2b512, Blake2s256};
 use blake3::Hasher as Blake3;
 use sha1::Sha1;
 use sha2::{Sha224, Sha256, Sha384, Sha512};
 use sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512};
+use xxhash_rust::xxh3::Xxh3;
 
 pub struct HashOptions {
     algorithm: HashAlgorithm,
@@ -17,7 +21,7 @@ pub struct HashOptions {
 }
 
 #[derive(Debug, Clone, Copy)]
-enum HashAlgorithm {
+pub enum HashAlgorithm {
     Md5,
     Sha1,
     // SHA-2 variants
@@ -30,12 +34,19 @@ enum HashAlgorithm {
     Sha3_256,
     Sha3_384,
     Sha3_512,
+    // BLAKE2 variants
+    Blake2b,
+    Blake2s,
     // Blake3
     Blake3,
+    // Non-cryptographic checksums
+    Crc32,
+    Xxh3_64,
+    Xxh3_128,
 }
 
 impl HashAlgorithm {
-    pub const ALL: [Self; 11] = [
+    pub const ALL: [Self; 16] = [
         Self::Md5,
         Self::Sha1,
         Self::Sha2_224,
@@ -46,9 +57,15 @@ impl HashAlgorithm {
         Self::Sha3_256,
         Self::Sha3_384,
         Self::Sha3_512,
+        Self::Blake2b,
+        Self::Blake2s,
         Self::Blake3,
+        Self::Crc32,
+        Self::Xxh3_64,
+        Self::Xxh3_128,
     ];
 
+    #[must_use]
     pub const fn name(self) -> &'static str {
         match self {
             Self::Md5 => "md5",
@@ -61,7 +78,67 @@ impl HashAlgorithm {
             Self::Sha3_256 => "sha3-256",
             Self::Sha3_384 => "sha3-384",
             Self::Sha3_512 => "sha3-512",
+            Self::Blake2b => "blake2b",
+            Self::Blake2s => "blake2s",
             Self::Blake3 => "blake3",
+            Self::Crc32 => "crc32",
+            Self::Xxh3_64 => "xxh3",
+            Self::Xxh3_128 => "xxh3-128",
+        }
+    }
+}
+
+/**
+    The incremental state of a hash being computed using some `HashAlgorithm`.
+*/
+enum HashState {
+    Digest(Box<dyn DynDigest>),
+    Crc32(crc32fast::Hasher),
+    Xxh3(Box<Xxh3>, bool),
+}
+
+impl HashState {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Md5 => Self::Digest(Box::new(Md5::default())),
+            HashAlgorithm::Sha1 => Self::Digest(Box::new(Sha1::default())),
+            HashAlgorithm::Sha2_224 => Self::Digest(Box::new(Sha224::default())),
+            HashAlgorithm::Sha2_256 => Self::Digest(Box::new(Sha256::default())),
+            HashAlgorithm::Sha2_384 => Self::Digest(Box::new(Sha384::default())),
+            HashAlgorithm::Sha2_512 => Self::Digest(Box::new(Sha512::default())),
+
+            HashAlgorithm::Sha3_224 => Self::Digest(Box::new(Sha3_224::default())),
+            HashAlgorithm::Sha3_256 => Self::Digest(Box::new(Sha3_256::default())),
+            HashAlgorithm::Sha3_384 => Self::Digest(Box::new(Sha3_384::default())),
+            HashAlgorithm::Sha3_512 => Self::Digest(Box::new(Sha3_512::default())),
+
+            HashAlgorithm::Blake2b => Self::Digest(Box::new(Blake2b512::default())),
+            HashAlgorithm::Blake2s => Self::Digest(Box::new(Blake2s256::default())),
+
+            HashAlgorithm::Blake3 => Self::Digest(Box::new(Blake3::default())),
+
+            HashAlgorithm::Crc32 => Self::Crc32(crc32fast::Hasher::new()),
+            HashAlgorithm::Xxh3_64 => Self::Xxh3(Box::new(Xxh3::new()), false),
+            HashAlgorithm::Xxh3_128 => Self::Xxh3(Box::new(Xxh3::new()), true),
+        }
+    }
+
+    fn update(&mut self, message: &[u8]) {
+        match self {
+            Self::Digest(d) => d.update(message),
+            Self::Crc32(h) => h.update(message),
+            Self::Xxh3(h, _) => h.update(message),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        // NOTE: Checksums are output in big-endian byte order, which is
+        // the canonical form that other tools display them in as hex
+        match self {
+            Self::Digest(d) => d.finalize().into_vec(),
+            Self::Crc32(h) => h.finalize().to_be_bytes().to_vec(),
+            Self::Xxh3(h, false) => h.digest().to_be_bytes().to_vec(),
+            Self::Xxh3(h, true) => h.digest128().to_be_bytes().to_vec(),
         }
     }
 }
@@ -74,24 +151,9 @@ impl HashOptions {
     #[inline]
     #[must_use = "hashing a message is useless without using the resulting hash"]
     pub fn hash(self) -> String {
-        use digest::Digest;
-
-        let message = self.message;
-        let bytes = match self.algorithm {
-            HashAlgorithm::Md5 => Md5::digest(message).to_vec(),
-            HashAlgorithm::Sha1 => Sha1::digest(message).to_vec(),
-            HashAlgorithm::Sha2_224 => Sha224::digest(message).to_vec(),
-            HashAlgorithm::Sha2_256 => Sha256::digest(message).to_vec(),
-            HashAlgorithm::Sha2_384 => Sha384::digest(message).to_vec(),
-            HashAlgorithm::Sha2_512 => Sha512::digest(message).to_vec(),
-
-            HashAlgorithm::Sha3_224 => Sha3_224::digest(message).to_vec(),
-            HashAlgorithm::Sha3_256 => Sha3_256::digest(message).to_vec(),
-            HashAlgorithm::Sha3_384 => Sha3_384::digest(message).to_vec(),
-            HashAlgorithm::Sha3_512 => Sha3_512::digest(message).to_vec(),
-
-            HashAlgorithm::Blake3 => Blake3::digest(message).to_vec(),
-        };
+        let mut state = HashState::new(self.algorithm);
+        state.update(&self.message);
+        let bytes = state.finalize();
 
         // We don't want to return raw binary data generally, since that's not
         // what most people want a hash for. So we have to make a hex string.
@@ -110,7 +172,8 @@ impl HashOptions {
 
         # Errors
 
-        If the `secret` is not provided or is otherwise invalid.
+        If the `secret` is not provided or is otherwise invalid,
+        or if the `algorithm` is not a cryptographic hash.
     */
     #[inline]
     pub fn hmac(self) -> LuaResult<String> {
@@ -163,7 +226,17 @@ impl HashOptions {
             HashAlgorithm::Sha3_384 => hmac!(Sha3_384),
             HashAlgorithm::Sha3_512 => hmac!(Sha3_512),
 
+            HashAlgorithm::Blake2b => hmac_no_blocks!(Blake2b512),
+            HashAlgorithm::Blake2s => hmac_no_blocks!(Blake2s256),
+
             HashAlgorithm::Blake3 => hmac_no_blocks!(Blake3),
+
+            HashAlgorithm::Crc32 | HashAlgorithm::Xxh3_64 | HashAlgorithm::Xxh3_128 => {
+                return Err(LuaError::RuntimeError(format!(
+                    "HMAC is not supported for the non-cryptographic hashing algorithm '{}'",
+                    self.algorithm.name()
+                )));
+            }
         };
         Ok(bytes
             .iter()
@@ -196,8 +269,15 @@ impl FromLua for HashAlgorithm {
                 "sha3-384" | "sha3_384" => Ok(Self::Sha3_384),
                 "sha3-512" | "sha3_512" => Ok(Self::Sha3_512),
 
+                "blake2b" | "blake2b-512" | "blake2b_512" => Ok(Self::Blake2b),
+                "blake2s" | "blake2s-256" | "blake2s_256" => Ok(Self::Blake2s),
+
                 "blake3" => Ok(Self::Blake3),
 
+                "crc32" => Ok(Self::Crc32),
+                "xxh3" | "xxh3-64" | "xxh3_64" => Ok(Self::Xxh3_64),
+                "xxh3-128" | "xxh3_128" => Ok(Self::Xxh3_128),
+
                 _ => Err(LuaError::FromLuaConversionError {
                     from: "string",
                     to: "HashAlgorithm".to_string(),
@@ -258,3 +338,104 @@ impl FromLuaMulti for HashOptions {
         })
     }
 }
+
+/**
+    The encoding used for the output of a `Hasher`.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub enum HashEncoding {
+    #[default]
+    Hex,
+    Base64,
+    Raw,
+}
+
+impl HashEncoding {
+    fn encode(self, bytes: Vec<u8>) -> Vec<u8> {
+        match self {
+            Self::Hex => bytes
+                .iter()
+                .fold(String::with_capacity(bytes.len() * 2), |mut output, b| {
+                    let _ = write!(output, "{b:02x}");
+                    output
+                })
+                .into_bytes(),
+            Self::Base64 => BASE64.encode(bytes).into_bytes(),
+            Self::Raw => bytes,
+        }
+    }
+}
+
+impl FromLua for HashEncoding {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        match &value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::String(s) => match s.to_string_lossy().to_ascii_lowercase().trim() {
+                "hex" => Ok(Self::Hex),
+                "base64" => Ok(Self::Base64),
+                "raw" => Ok(Self::Raw),
+                kind => Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "HashEncoding".to_string(),
+                    message: Some(format!(
+                        "Invalid hash encoding '{kind}', valid encodings are: hex, base64, raw"
+                    )),
+                }),
+            },
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "HashEncoding".to_string(),
+                message: None,
+            }),
+        }
+    }
+}
+
+/**
+    An incremental hasher, which hashes chunks of bytes as they are given.
+*/
+#[derive(Clone)]
+pub struct Hasher {
+    algorithm: HashAlgorithm,
+    state: Rc<RefCell<Option<HashState>>>,
+}
+
+impl Hasher {
+    /**
+        Creates a new hasher for the given algorithm.
+    */
+    #[must_use]
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self {
+            algorithm,
+            state: Rc::new(RefCell::new(Some(HashState::new(algorithm)))),
+        }
+    }
+}
+
+impl LuaUserData for Hasher {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("algorithm", |_, this| Ok(this.algorithm.name()));
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("update", |_, this, chunk: BString| {
+            let mut state = this.state.borrow_mut();
+            let Some(state) = state.as_mut() else {
+                return Err(finished_error());
+            };
+            state.update(&chunk);
+            Ok(())
+        });
+        methods.add_method("digest", |lua, this, encoding: HashEncoding| {
+            let Some(state) = this.state.borrow_mut().take() else {
+                return Err(finished_error());
+            };
+            lua.create_string(encoding.encode(state.finalize()))
+        });
+    }
+}
+
+fn finished_error() -> LuaError {
+    LuaError::RuntimeError("Hasher has already been finalized using digest".to_string())
+}
@@ -9,9 +9,14 @@ mod compress_decompress;
 mod encode_decode;
 mod hash;
 
-pub use self::compress_decompress::{CompressDecompressFormat, compress, decompress};
-pub use self::encode_decode::{EncodeDecodeConfig, EncodeDecodeFormat, decode, encode};
-pub use self::hash::HashOptions;
+pub use self::compress_decompress::{
+    CompressDecompressFormat, Compressor, Decompressor, compress, decompress,
+};
+pub use self::encode_decode::{
+    CsvHeader, CsvOptions, EmptyTables, EncodeDecodeConfig, EncodeDecodeFormat,
+    EncodeDecodeOptions, OrderedTables, decode, encode,
+};
+pub use self::hash::{HashAlgorithm, HashEncoding, HashOptions, Hasher};
 
 const TYPEDEFS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/types.d.luau"));
 
@@ -31,27 +36,49 @@ pub fn typedefs() -> String {
     Errors when out of memory.
 */
 pub fn module(lua: Lua) -> LuaResult<LuaTable> {
-    TableBuilder::new(lua)?
+    TableBuilder::new(lua.clone())?
         .with_function("encode", serde_encode)?
         .with_function("decode", serde_decode)?
+        .with_value("null", lua.null())?
+        .with_function("array", serde_array)?
+        .with_function("ordered", serde_ordered)?
         .with_async_function("compress", serde_compress)?
         .with_async_function("decompress", serde_decompress)?
+        .with_function("compressor", serde_compressor)?
+        .with_function("decompressor", serde_decompressor)?
         .with_function("hash", hash_message)?
         .with_function("hmac", hmac_message)?
+        .with_function("hasher", serde_hasher)?
         .build_readonly()
 }
 
 fn serde_encode(
     lua: &Lua,
-    (format, value, pretty): (EncodeDecodeFormat, LuaValue, Option<bool>),
+    (format, value, options): (EncodeDecodeFormat, LuaValue, EncodeDecodeOptions),
 ) -> LuaResult<LuaString> {
-    let config = EncodeDecodeConfig::from((format, pretty.unwrap_or_default()));
-    encode(value, lua, config)
+    let config = EncodeDecodeConfig::from((format, options));
+    encode(value, lua, &config)
 }
 
-fn serde_decode(lua: &Lua, (format, bs): (EncodeDecodeFormat, BString)) -> LuaResult<LuaValue> {
-    let config = EncodeDecodeConfig::from(format);
-    decode(bs, lua, config)
+fn serde_decode(
+    lua: &Lua,
+    (format, bs, options): (EncodeDecodeFormat, BString, EncodeDecodeOptions),
+) -> LuaResult<LuaValue> {
+    let config = EncodeDecodeConfig::from((format, options));
+    decode(bs, lua, &config)
+}
+
+fn serde_array(lua: &Lua, table: Option<LuaTable>) -> LuaResult<LuaTable> {
+    let table = match table {
+        Some(table) => table,
+        None => lua.create_table()?,
+    };
+    table.set_metatable(Some(lua.array_metatable()))?;
+    Ok(table)
+}
+
+fn serde_ordered(lua: &Lua, _: ()) -> LuaResult<LuaTable> {
+    OrderedTables::get_or_init(lua)?.create_table(lua)
 }
 
 async fn serde_compress(
@@ -70,6 +97,20 @@ async fn serde_decompress(
     lua.create_string(bytes)
 }
 
+fn serde_compressor(
+    _: &Lua,
+    (format, level): (CompressDecompressFormat, Option<i32>),
+) -> LuaResult<Compressor> {
+    Compressor::new(format, level)
+}
+
+fn serde_decompressor(
+    _: &Lua,
+    format: Option<CompressDecompressFormat>,
+) -> LuaResult<Decompressor> {
+    Decompressor::new(format)
+}
+
 fn hash_message(lua: &Lua, options: HashOptions) -> LuaResult<LuaString> {
     lua.create_string(options.hash())
 }
@@ -77,3 +118,7 @@ fn hash_message(lua: &Lua, options: HashOptions) -> LuaResult<LuaString> {
 fn hmac_message(lua: &Lua, options: HashOptions) -> LuaResult<LuaString> {
     lua.create_string(options.hmac()?)
 }
+
+fn serde_hasher(_: &Lua, algorithm: HashAlgorithm) -> LuaResult<Hasher> {
+    Ok(Hasher::new(algorithm))
+}
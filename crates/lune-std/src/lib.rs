@@ -43,3 +43,14 @@ pub fn inject_std(lua: Lua) -> LuaResult<()> {
     }
     Ok(())
 }
+
+/**
+    Cleans up any resources left behind by standard libraries in the given Lua state / VM.
+
+    This should be called once the runtime has finished running all threads.
+*/
+#[cfg_attr(not(feature = "fs"), allow(unused_variables))]
+pub fn teardown_std(lua: &Lua) {
+    #[cfg(feature = "fs")]
+    lune_std_fs::cleanup(lua);
+}
@@ -3,15 +3,17 @@
 */
 
 use std::{
-    env::{current_dir, current_exe},
+    env::{self, current_dir, current_exe},
     ffi::OsStr,
+    io,
     path::{Component, MAIN_SEPARATOR, Path, PathBuf},
-    sync::{Arc, LazyLock},
+    sync::{Arc, LazyLock, PoisonError, RwLock},
 };
 
 use path_clean::PathClean;
 
-static CWD: LazyLock<Arc<Path>> = LazyLock::new(create_cwd);
+static INITIAL_CWD: LazyLock<Arc<Path>> = LazyLock::new(create_cwd);
+static CWD: LazyLock<RwLock<Arc<Path>>> = LazyLock::new(|| RwLock::new(Arc::clone(&INITIAL_CWD)));
 static EXE: LazyLock<Arc<Path>> = LazyLock::new(create_exe);
 
 fn create_cwd() -> Arc<Path> {
@@ -52,7 +54,40 @@ fn create_exe() -> Arc<Path> {
 */
 #[must_use]
 pub fn get_current_dir() -> Arc<Path> {
-    Arc::clone(&CWD)
+    let cwd = CWD.read().unwrap_or_else(PoisonError::into_inner);
+    Arc::clone(&cwd)
+}
+
+/**
+    Changes the current working directory of the process.
+
+    Relative paths are resolved against the current working directory.
+
+    Returns the new current working directory, which has the
+    same guarantees as paths returned by [`get_current_dir`].
+
+    # Errors
+
+    Errors if the path does not exist, is not a directory,
+    is not valid UTF-8, or the directory could not be changed.
+*/
+pub fn set_current_dir(path: impl AsRef<Path>) -> io::Result<Arc<Path>> {
+    // NOTE: Make sure the initial directory is known before changing it
+    LazyLock::force(&INITIAL_CWD);
+
+    let mut cwd = CWD.write().unwrap_or_else(PoisonError::into_inner);
+
+    let path = dunce::canonicalize(cwd.join(path))?;
+    if path.to_str().is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "working directory is not valid UTF-8",
+        ));
+    }
+    env::set_current_dir(&path)?;
+
+    *cwd = path.into();
+    Ok(Arc::clone(&cwd))
 }
 
 /**
@@ -83,7 +118,9 @@ pub fn clean_path(path: impl AsRef<Path>) -> PathBuf {
 /**
     Makes a path absolute, if it is relative, and then cleans it.
 
-    Relative paths are resolved against the current working directory.
+    Relative paths are resolved against the initial working directory, since
+    paths for Lua chunks and modules are always relative to it, and must keep
+    pointing to the same files even if the current working directory changes.
 
     See the [`path_clean`] crate for more information on what cleaning a path does.
 */
@@ -91,7 +128,7 @@ pub fn clean_path(path: impl AsRef<Path>) -> PathBuf {
 pub fn clean_path_and_make_absolute(path: impl AsRef<Path>) -> PathBuf {
     let path = path.as_ref();
     if path.is_relative() {
-        CWD.join(path).clean()
+        INITIAL_CWD.join(path).clean()
     } else {
         path.clean()
     }
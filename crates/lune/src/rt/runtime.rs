@@ -95,6 +95,7 @@ impl Runtime {
 
         // Inject all the globals that are enabled
         #[cfg(any(
+            feature = "std-archive",
             feature = "std-datetime",
             feature = "std-fs",
             feature = "std-luau",
@@ -117,6 +118,7 @@ impl Runtime {
         // _G table needs to be injected again after sandboxing,
         // otherwise it will be read-only and completely unusable
         #[cfg(any(
+            feature = "std-archive",
             feature = "std-datetime",
             feature = "std-fs",
             feature = "std-luau",
@@ -353,6 +355,7 @@ impl Runtime {
         // Inject all the standard libraries that are enabled - this needs to be done after
         // storing the args/env, since some standard libraries use those during initialization
         #[cfg(any(
+            feature = "std-archive",
             feature = "std-datetime",
             feature = "std-fs",
             feature = "std-luau",
@@ -381,6 +384,24 @@ impl Runtime {
         let main_thread_id = self.sched.push_thread_back(main, ())?;
         self.sched.run().await;
 
+        // Clean up anything that the standard libraries left behind, such as temporary files
+        #[cfg(any(
+            feature = "std-archive",
+            feature = "std-datetime",
+            feature = "std-fs",
+            feature = "std-luau",
+            feature = "std-net",
+            feature = "std-process",
+            feature = "std-regex",
+            feature = "std-roblox",
+            feature = "std-serde",
+            feature = "std-stdio",
+            feature = "std-task",
+        ))]
+        {
+            lune_std::teardown_std(&self.lua);
+        }
+
         let main_thread_values = self
             .sched
             .get_thread_result(main_thread_id)
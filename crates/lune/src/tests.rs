@@ -45,6 +45,7 @@ macro_rules! create_tests {
 }
 
 #[cfg(any(
+    feature = "std-archive",
     feature = "std-datetime",
     feature = "std-fs",
     feature = "std-luau",
@@ -81,6 +82,12 @@ create_tests! {
     global_warn: "globals/warn",
 }
 
+#[cfg(feature = "std-archive")]
+create_tests! {
+    archive_tar: "archive/tar",
+    archive_zip: "archive/zip",
+}
+
 #[cfg(feature = "std-datetime")]
 create_tests! {
     datetime_format_local_time: "datetime/formatLocalTime",
@@ -100,10 +107,16 @@ create_tests! {
 #[cfg(feature = "std-fs")]
 create_tests! {
     fs_files: "fs/files",
+    fs_links: "fs/links",
     fs_copy: "fs/copy",
     fs_dirs: "fs/dirs",
     fs_metadata: "fs/metadata",
     fs_move: "fs/move",
+    fs_open: "fs/open",
+    fs_permissions: "fs/permissions",
+    fs_temp: "fs/temp",
+    fs_walk: "fs/walk",
+    fs_watch: "fs/watch",
 }
 
 #[cfg(feature = "std-luau")]
@@ -116,17 +129,25 @@ create_tests! {
 
 #[cfg(feature = "std-net")]
 create_tests! {
+    net_request_cancel: "net/request/cancel",
     net_request_codes: "net/request/codes",
     net_request_compression: "net/request/compression",
     net_request_https: "net/request/https",
+    net_request_max_redirects: "net/request/max_redirects",
     net_request_methods: "net/request/methods",
+    net_request_pool: "net/request/pool",
     net_request_query: "net/request/query",
     net_request_redirect: "net/request/redirect",
+    net_request_retries: "net/request/retries",
+    net_request_timeout: "net/request/timeout",
+    net_request_tls: "net/request/tls",
 
     net_serve_addresses: "net/serve/addresses",
     net_serve_handles: "net/serve/handles",
+    net_serve_http2: "net/serve/http2",
     net_serve_non_blocking: "net/serve/non_blocking",
     net_serve_requests: "net/serve/requests",
+    net_serve_tls: "net/serve/tls",
     net_serve_websockets: "net/serve/websockets",
 
     net_socket_basic: "net/socket/basic",
@@ -147,14 +168,26 @@ create_tests! {
     process_cwd: "process/cwd",
     process_env: "process/env",
     process_exit: "process/exit",
+    process_on_signal: "process/on_signal",
+    process_parse_args: "process/parse_args",
+    process_pipeline: "process/pipeline",
+    process_stats: "process/stats",
+    process_which: "process/which",
     process_exec_async: "process/exec/async",
     process_exec_basic: "process/exec/basic",
+    process_exec_callbacks: "process/exec/callbacks",
     process_exec_cwd: "process/exec/cwd",
+    process_exec_limits: "process/exec/limits",
     process_exec_no_panic: "process/exec/no_panic",
     process_exec_shell: "process/exec/shell",
     process_exec_stdin: "process/exec/stdin",
     process_exec_stdio: "process/exec/stdio",
+    process_exec_timeout: "process/exec/timeout",
+    process_spawn_detached: "process/create/detached",
+    process_spawn_lines: "process/create/lines",
     process_spawn_non_blocking: "process/create/non_blocking",
+    process_spawn_pty: "process/create/pty",
+    process_spawn_signal: "process/create/signal",
     process_spawn_status: "process/create/status",
     process_spawn_stream: "process/create/stream",
 }
@@ -241,14 +274,21 @@ create_tests! {
 create_tests! {
     serde_compression_files: "serde/compression/files",
     serde_compression_roundtrip: "serde/compression/roundtrip",
+    serde_compression_stream: "serde/compression/stream",
+    serde_cbor_roundtrip: "serde/cbor/roundtrip",
+    serde_csv_decode: "serde/csv/decode",
+    serde_csv_encode: "serde/csv/encode",
     serde_json_decode: "serde/json/decode",
     serde_json_encode: "serde/json/encode",
+    serde_json_options: "serde/json/options",
     serde_jsonc_decode: "serde/jsonc/decode",
     serde_jsonc_encode: "serde/jsonc/encode",
+    serde_msgpack_roundtrip: "serde/msgpack/roundtrip",
     serde_toml_decode: "serde/toml/decode",
     serde_toml_encode: "serde/toml/encode",
     serde_hashing_hash: "serde/hashing/hash",
     serde_hashing_hmac: "serde/hashing/hmac",
+    serde_hashing_hasher: "serde/hashing/hasher",
 }
 
 #[cfg(feature = "std-stdio")]
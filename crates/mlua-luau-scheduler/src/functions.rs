@@ -5,7 +5,7 @@ use mlua::prelude::*;
 use crate::{
     error_callback::ThreadErrorCallback,
     queue::{DeferredThreadQueue, SpawnedThreadQueue},
-    threads::{ThreadId, ThreadMap},
+    threads::{ThreadCancelMap, ThreadId, ThreadMap},
     traits::LuaSchedulerExt,
     util::{LuaThreadOrFunction, is_poll_pending},
 };
@@ -66,6 +66,8 @@ pub struct Functions {
     pub defer: LuaFunction,
     /**
         Cancels a function / thread, removing it from the queue.
+
+        Also stops waiting on any async function that the thread is currently waiting on.
     */
     pub cancel: LuaFunction,
     /**
@@ -105,6 +107,10 @@ impl Functions {
             .app_data_ref::<ThreadMap>()
             .expect(ERR_METADATA_NOT_ATTACHED)
             .clone();
+        let cancel_map = lua
+            .app_data_ref::<ThreadCancelMap>()
+            .expect(ERR_METADATA_NOT_ATTACHED)
+            .clone();
 
         let resume_queue = defer_queue.clone();
         let resume_map = thread_map.clone();
@@ -212,8 +218,12 @@ impl Functions {
         let cancel = lua.create_function(move |lua, thread: LuaThread| {
             let _span = tracing::trace_span!("Scheduler::fn_cancel").entered();
             let close: LuaFunction = lua.registry_value(&close_key)?;
+            let id = ThreadId::from(&thread);
             match close.call(thread) {
-                Err(LuaError::CoroutineUnresumable) | Ok(()) => Ok(()),
+                Err(LuaError::CoroutineUnresumable) | Ok(()) => {
+                    cancel_map.cancel(id);
+                    Ok(())
+                }
                 Err(e) => Err(e),
             }
         })?;
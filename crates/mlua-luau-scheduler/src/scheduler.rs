@@ -18,7 +18,7 @@ use crate::{
     exit::Exit,
     queue::{DeferredThreadQueue, FuturesQueue, SpawnedThreadQueue},
     status::Status,
-    threads::{ThreadId, ThreadMap},
+    threads::{ThreadCancelMap, ThreadId, ThreadMap},
     traits::IntoLuaThread,
     util::run_until_yield,
 };
@@ -48,6 +48,7 @@ pub struct Scheduler {
     queue_defer: DeferredThreadQueue,
     error_callback: ThreadErrorCallback,
     thread_map: ThreadMap,
+    cancel_map: ThreadCancelMap,
     status: Rc<Cell<Status>>,
     exit: Exit,
 }
@@ -68,6 +69,7 @@ impl Scheduler {
         let queue_defer = DeferredThreadQueue::new();
         let error_callback = ThreadErrorCallback::default();
         let result_map = ThreadMap::new();
+        let cancel_map = ThreadCancelMap::new();
         let exit = Exit::new();
 
         assert!(
@@ -86,6 +88,10 @@ impl Scheduler {
             lua.app_data_ref::<ThreadMap>().is_none(),
             "{ERR_METADATA_ALREADY_ATTACHED}"
         );
+        assert!(
+            lua.app_data_ref::<ThreadCancelMap>().is_none(),
+            "{ERR_METADATA_ALREADY_ATTACHED}"
+        );
         assert!(
             lua.app_data_ref::<Exit>().is_none(),
             "{ERR_METADATA_ALREADY_ATTACHED}"
@@ -95,6 +101,7 @@ impl Scheduler {
         lua.set_app_data(queue_defer.clone());
         lua.set_app_data(error_callback.clone());
         lua.set_app_data(result_map.clone());
+        lua.set_app_data(cancel_map.clone());
         lua.set_app_data(exit.clone());
 
         let status = Rc::new(Cell::new(Status::NotStarted));
@@ -105,6 +112,7 @@ impl Scheduler {
             queue_defer,
             error_callback,
             thread_map: result_map,
+            cancel_map,
             status,
             exit,
         }
@@ -326,6 +334,7 @@ impl Scheduler {
         */
         let fut = async {
             let result_map = self.thread_map.clone();
+            let cancel_map = self.cancel_map.clone();
             let process_thread = |thread: LuaThread, args| {
                 // NOTE: Thread may have been cancelled from Lua
                 // before we got here, so we need to check it again
@@ -338,25 +347,29 @@ impl Scheduler {
                     } else {
                         None
                     };
+                    // Listen for the thread being cancelled while it is running - cancelled threads
+                    // will never be resumed again, but whatever they were waiting on would
+                    // otherwise keep this future, and with it the whole scheduler, alive
+                    let cancel_map = cancel_map.clone();
+                    let cancelled = cancel_map.listen(id);
                     // Create our future which will run the thread and store its final result
                     let fut = async move {
-                        if id_tracked {
-                            // Run until yield and check if we got a final result
-                            if let Some(res) = run_until_yield(thread.clone(), args).await {
-                                if let Err(e) = res.as_ref() {
-                                    self.error_callback.call(e);
-                                }
-                                if thread.status() != LuaThreadStatus::Resumable {
-                                    result_map_inner.unwrap().insert(id, res);
-                                }
-                            }
-                        } else {
-                            // Just run until yield
-                            if let Some(res) = run_until_yield(thread, args).await
-                                && let Err(e) = res.as_ref()
-                            {
+                        // Run until yield or cancellation
+                        let res = run_until_yield(thread.clone(), args)
+                            .or(async move {
+                                cancelled.await;
+                                None
+                            })
+                            .await;
+                        cancel_map.remove(id);
+                        if let Some(res) = res {
+                            if let Err(e) = res.as_ref() {
                                 self.error_callback.call(e);
                             }
+                            // Check if we got a final result
+                            if id_tracked && thread.status() != LuaThreadStatus::Resumable {
+                                result_map_inner.unwrap().insert(id, res);
+                            }
                         }
                     };
                     // Spawn it on the executor
@@ -464,6 +477,7 @@ impl Drop for Scheduler {
             self.lua.remove_app_data::<DeferredThreadQueue>();
             self.lua.remove_app_data::<ThreadErrorCallback>();
             self.lua.remove_app_data::<ThreadMap>();
+            self.lua.remove_app_data::<ThreadCancelMap>();
             self.lua.remove_app_data::<Exit>();
         } else {
             // In any other case we panic if metadata was removed incorrectly
@@ -479,6 +493,9 @@ impl Drop for Scheduler {
             self.lua
                 .remove_app_data::<ThreadMap>()
                 .expect(ERR_METADATA_REMOVED);
+            self.lua
+                .remove_app_data::<ThreadCancelMap>()
+                .expect(ERR_METADATA_REMOVED);
             self.lua
                 .remove_app_data::<Exit>()
                 .expect(ERR_METADATA_REMOVED);
@@ -0,0 +1,51 @@
+#![allow(clippy::inline_always)]
+
+use std::{cell::RefCell, rc::Rc};
+
+use rustc_hash::FxHashMap;
+
+use crate::events::{OnceEvent, OnceListener};
+
+use super::id::ThreadId;
+
+/**
+    Cancellation events for threads that are currently waiting on a future.
+
+    Threads are only present in this map while something is listening for
+    their cancellation, so that cancelling them can stop whatever they were
+    waiting on, instead of leaving it to run to completion in the background.
+*/
+#[derive(Clone)]
+pub(crate) struct ThreadCancelMap {
+    inner: Rc<RefCell<FxHashMap<ThreadId, OnceEvent>>>,
+}
+
+impl ThreadCancelMap {
+    pub fn new() -> Self {
+        let inner = Rc::new(RefCell::new(FxHashMap::default()));
+        Self { inner }
+    }
+
+    #[inline(always)]
+    #[allow(clippy::unwrap_or_default)] // NOTE: Default events are already notified
+    pub fn listen(&self, id: ThreadId) -> OnceListener {
+        self.inner
+            .borrow_mut()
+            .entry(id)
+            .or_insert_with(OnceEvent::new)
+            .listen()
+    }
+
+    #[inline(always)]
+    pub fn cancel(&self, id: ThreadId) {
+        let event = self.inner.borrow_mut().remove(&id);
+        if let Some(event) = event {
+            event.notify();
+        }
+    }
+
+    #[inline(always)]
+    pub fn remove(&self, id: ThreadId) {
+        self.inner.borrow_mut().remove(&id);
+    }
+}
@@ -13,7 +13,7 @@ use crate::{
     exit::Exit,
     queue::{DeferredThreadQueue, FuturesQueue, SpawnedThreadQueue},
     scheduler::Scheduler,
-    threads::{ThreadId, ThreadMap},
+    threads::{ThreadCancelMap, ThreadId, ThreadMap},
 };
 
 /**
@@ -141,6 +141,20 @@ pub trait LuaSchedulerExt {
         Panics if called outside of a running [`Scheduler`].
     */
     fn wait_for_thread(&self, id: ThreadId) -> impl Future<Output = ()>;
+
+    /**
+        Waits for the given thread to be cancelled.
+
+        Cancelled threads are never resumed again, so async functions may race their work
+        against this to stop it as soon as the thread calling them has been cancelled.
+
+        Note that this only ever completes if the thread is cancelled, and not if it completes.
+
+        # Panics
+
+        Panics if called outside of a running [`Scheduler`].
+    */
+    fn wait_for_cancel(&self, id: ThreadId) -> impl Future<Output = ()> + 'static;
 }
 
 /**
@@ -331,6 +345,13 @@ impl LuaSchedulerExt for Lua {
             .expect("lua threads results can only be retrieved from within an active scheduler");
         map.listen(id)
     }
+
+    fn wait_for_cancel(&self, id: ThreadId) -> impl Future<Output = ()> + 'static {
+        let map = self
+            .app_data_ref::<ThreadCancelMap>()
+            .expect("lua threads can only be cancelled from within an active scheduler");
+        map.listen(id)
+    }
 }
 
 impl LuaSpawnExt for Lua {